@@ -1,53 +1,267 @@
-use crate::models::Connection;
+use crate::config::{refresh_connections, Snapshot};
+use crate::form::ConnectionForm;
+use crate::models::{usage, Connection, Usage};
+use crate::query::{haystack, Query};
+use crate::tunnels::Tunnel;
+use anyhow::Result;
 use fuzzy_matcher::skim::SkimMatcherV2;
 use fuzzy_matcher::FuzzyMatcher;
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap, HashSet};
 
 #[derive(Default)]
 pub struct AppState {
     pub connections: Vec<Connection>,
+    /// connections.json as last loaded or saved, to merge concurrent edits
+    pub saved: Snapshot,
+    /// Keyed by connection name
+    pub usage: HashMap<String, Usage>,
+    /// Order by name instead of frecency
+    pub sort_by_name: bool,
     pub filtered_indices: Vec<usize>,
+    /// Fuzzy-matched char positions in `query::haystack`, by connection index
+    pub match_indices: HashMap<usize, Vec<usize>>,
+    /// What the list shows: `filtered_indices` arranged as a group tree, or
+    /// flat while searching. `selected` indexes into this.
+    pub rows: Vec<Row>,
+    /// Group paths whose children are hidden
+    pub collapsed: HashSet<String>,
     pub selected: usize,
     pub search: String,
     pub mode: Mode,
     pub show_help: bool,
+    /// First visible line of the help popup, when it doesn't fit
+    pub help_scroll: u16,
+    /// Detail view of the selected connection
+    pub show_details: bool,
     pub pending_delete: bool,
-    pub add_buffer: String,
+    pub form: ConnectionForm,
+    /// Popup list of choices for the focused form field, e.g. ssh keys
+    pub picker: Option<Picker>,
     pub status: String,
+    /// Shell-quoted ssh command shown by the dry-run popup
+    pub dry_run: Option<String>,
+    /// Text of the "connect with command" prompt
+    pub command: String,
+    /// Background `ssh -N` sessions, stopped when the app exits
+    pub tunnels: Vec<Tunnel>,
+    pub tunnel_selected: usize,
 }
 
-#[derive(PartialEq)]
+#[derive(PartialEq, Default)]
 pub enum Mode {
+    #[default]
     Normal,
     Search,
     Add,
+    Edit,
+    /// Keyboard focus is on the Tunnels panel
+    Tunnels,
+    /// Typing a one-off remote command to connect with
+    Command,
 }
 
-impl Default for Mode {
-    fn default() -> Self {
-        Mode::Normal
+pub struct Picker {
+    pub title: String,
+    pub items: Vec<String>,
+    pub selected: usize,
+}
+
+pub enum Row {
+    Group {
+        /// Full path, e.g. `prod/eu`
+        path: String,
+        depth: usize,
+        /// Connections anywhere below this group
+        count: usize,
+    },
+    Connection {
+        index: usize,
+        depth: usize,
+    },
+}
+
+#[derive(Default)]
+struct GroupNode {
+    children: BTreeMap<String, GroupNode>,
+    conns: Vec<usize>,
+}
+
+impl GroupNode {
+    fn count(&self) -> usize {
+        self.conns.len() + self.children.values().map(GroupNode::count).sum::<usize>()
     }
 }
 
 impl AppState {
+    /// Index into `connections` of the selected row, if it is a connection.
+    pub fn selected_connection(&self) -> Option<usize> {
+        match self.rows.get(self.selected) {
+            Some(Row::Connection { index, .. }) => Some(*index),
+            _ => None,
+        }
+    }
+
+    /// Move the selection to the row showing `connections[index]`, if visible.
+    pub fn select_connection(&mut self, index: usize) {
+        if let Some(i) = self
+            .rows
+            .iter()
+            .position(|r| matches!(r, Row::Connection { index: i, .. } if *i == index))
+        {
+            self.selected = i;
+        }
+    }
+
+    pub fn selected_group(&self) -> Option<&str> {
+        match self.rows.get(self.selected) {
+            Some(Row::Group { path, .. }) => Some(path),
+            _ => None,
+        }
+    }
+
+    pub fn toggle_group(&mut self, path: &str) {
+        if !self.collapsed.remove(path) {
+            self.collapsed.insert(path.to_string());
+        }
+        self.build_rows();
+        self.select_group(path);
+    }
+
+    pub fn set_collapsed(&mut self, path: &str, collapsed: bool) {
+        if collapsed {
+            self.collapsed.insert(path.to_string());
+        } else {
+            self.collapsed.remove(path);
+        }
+        self.build_rows();
+        self.select_group(path);
+    }
+
+    fn select_group(&mut self, path: &str) {
+        if let Some(i) = self
+            .rows
+            .iter()
+            .position(|r| matches!(r, Row::Group { path: p, .. } if p == path))
+        {
+            self.selected = i;
+        }
+    }
+
+    /// Pick up changes made to connections.json outside this instance. The
+    /// search stays, and so does the selected connection or group if it is
+    /// still there. Returns whether anything was reloaded.
+    pub fn reload(&mut self) -> Result<bool> {
+        let name = self
+            .selected_connection()
+            .map(|i| self.connections[i].name.clone());
+        let group = self.selected_group().map(str::to_string);
+        let row = self.selected;
+        if !refresh_connections(&mut self.saved, &mut self.connections)? {
+            return Ok(false);
+        }
+        self.apply_filter();
+        self.selected = row.min(self.rows.len().saturating_sub(1));
+        if let Some(i) = name.and_then(|n| self.connections.iter().position(|c| c.name == n)) {
+            self.select_connection(i);
+        } else if let Some(path) = group {
+            self.select_group(&path);
+        }
+        Ok(true)
+    }
+
+    /// Lay out `filtered_indices` as rows: a group tree when browsing, a flat
+    /// ranked list while a search is active.
+    fn build_rows(&mut self) {
+        if !self.search.trim().is_empty() {
+            self.rows = self
+                .filtered_indices
+                .iter()
+                .map(|&index| Row::Connection { index, depth: 0 })
+                .collect();
+            return;
+        }
+
+        let mut root = GroupNode::default();
+        for &i in &self.filtered_indices {
+            let mut node = &mut root;
+            for part in self.connections[i].group_path() {
+                node = node.children.entry(part.to_string()).or_default();
+            }
+            node.conns.push(i);
+        }
+
+        fn walk(
+            node: &GroupNode,
+            prefix: &str,
+            depth: usize,
+            collapsed: &HashSet<String>,
+            rows: &mut Vec<Row>,
+        ) {
+            for (name, child) in &node.children {
+                let path = if prefix.is_empty() {
+                    name.clone()
+                } else {
+                    format!("{}/{}", prefix, name)
+                };
+                rows.push(Row::Group {
+                    path: path.clone(),
+                    depth,
+                    count: child.count(),
+                });
+                if !collapsed.contains(&path) {
+                    walk(child, &path, depth + 1, collapsed, rows);
+                }
+            }
+            rows.extend(node.conns.iter().map(|&index| Row::Connection { index, depth }));
+        }
+
+        let mut rows = Vec::new();
+        walk(&root, "", 0, &self.collapsed, &mut rows);
+        self.rows = rows;
+        if self.selected >= self.rows.len() {
+            self.selected = self.rows.len().saturating_sub(1);
+        }
+    }
+
     pub fn apply_filter(&mut self) {
         let matcher = SkimMatcherV2::default();
-        let q = self.search.trim();
-        if q.is_empty() {
-            self.filtered_indices = (0..self.connections.len()).collect();
-            self.selected = 0.min(self.filtered_indices.len().saturating_sub(1));
-            return;
+        let query = Query::parse(&self.search);
+        let q = query.fuzzy_text();
+        let now = usage::now();
+
+        self.match_indices.clear();
+        let mut scored: Vec<(i64, usize)> = Vec::new();
+        for (i, c) in self.connections.iter().enumerate() {
+            if !query.matches(c) {
+                continue;
+            }
+            if q.is_empty() {
+                scored.push((0, i));
+            } else if let Some((score, indices)) = matcher.fuzzy_indices(&haystack(c), &q) {
+                scored.push((score, i));
+                self.match_indices.insert(i, indices);
+            }
+        }
+        // Best fuzzy score first; ties (and an empty query, where every score
+        // is 0) go by frecency or name. The sort is stable, so anything still
+        // tied keeps the saved order.
+        if self.sort_by_name {
+            scored.sort_by_cached_key(|&(score, i)| {
+                (Reverse(score), self.connections[i].name.to_lowercase())
+            });
+        } else {
+            scored.sort_by_cached_key(|&(score, i)| {
+                let frecency = self
+                    .usage
+                    .get(&self.connections[i].name)
+                    .map(|u| u.frecency(now))
+                    .unwrap_or_default();
+                (Reverse(score), Reverse(frecency))
+            });
         }
-        let mut scored: Vec<(i64, usize)> = self
-            .connections
-            .iter()
-            .enumerate()
-            .filter_map(|(i, c)| {
-                let hay = c.label();
-                matcher.fuzzy_match(&hay, q).map(|score| (score, i))
-            })
-            .collect();
-        scored.sort_by(|a, b| b.0.cmp(&a.0));
         self.filtered_indices = scored.into_iter().map(|(_, i)| i).collect();
-        self.selected = 0.min(self.filtered_indices.len().saturating_sub(1));
+        self.selected = 0;
+        self.build_rows();
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,412 @@
+use anyhow::{bail, Context, Result};
+use std::{path::PathBuf, process};
+
+use crate::app::AppState;
+use crate::completions;
+use crate::config::{
+    load_connections, load_usage, lock_config, merge_connections, rename_usage, save_connections,
+};
+use crate::form::{ConnectionForm, Field};
+use crate::models::{jump_dependents, rename_jump_host, Connection};
+use crate::pick;
+use crate::ssh::{command_preview, exec_ssh, SshCommand};
+use crate::ssh_config::{
+    default_ssh_config_path, load_ssh_config, render_ssh_config, write_managed_section,
+};
+
+const USAGE: &str = "\
+Usage: jumpseat [command]
+
+Without a command, starts the interactive TUI.
+
+Commands:
+  list [--json] [query]    List connections, filtered and ranked like the TUI search
+  show [--json] <query>    Show one connection and the command that connects to it
+  connect [--print] <query> [command...]
+                           Connect, optionally running a command; <query> is a name
+                           or a fuzzy search that must match exactly one connection
+  add <name> <host> [options]
+                           Add a connection; <host> may be user@host:port
+  edit <name> [options]    Change settings of a connection
+  rm <name>...             Remove connections
+  pick [--format name|dest|argv] [query]
+                           Choose a connection interactively and print it, e.g.
+                           ssh $(jumpseat pick --format dest); exits 130 on cancel
+  tag <name> [+tag|-tag]...
+                           Show, add or remove tags on a connection
+  import [path]            Import hosts from an OpenSSH config (default: ~/.ssh/config)
+  export                   Print a Host stanza for every connection
+  export --write [path]    Write them into a managed section of an OpenSSH config
+                           (default: ~/.ssh/config)
+  completions <bash|zsh|fish>
+                           Print a shell completion script
+  --print <query>          Same as connect --print
+
+Options for add and edit (list options repeat or take comma/semicolon lists):
+  --name <name>  --host <host>  --user <user>  --port <port>  --group <a/b>
+  --tag <tag>  --key <file>  --via <jump host>  --client <ssh|mosh|et|binary>
+  --fwd '<L|R|D spec>'  --cmd <remote command>  --term <term>
+  --env NAME=value  --opt Key=Value";
+
+/// Flags of `add` and `edit`, and the form field each one fills.
+pub const FIELD_FLAGS: &[(&str, Field)] = &[
+    ("--name", Field::Name),
+    ("--group", Field::Group),
+    ("--user", Field::User),
+    ("--host", Field::Host),
+    ("--port", Field::Port),
+    ("--term", Field::Term),
+    ("--env", Field::Env),
+    ("--tag", Field::Tags),
+    ("--key", Field::Identity),
+    ("--via", Field::Jump),
+    ("--client", Field::Transport),
+    ("--fwd", Field::Forwards),
+    ("--cmd", Field::Command),
+    ("--opt", Field::Options),
+];
+
+pub fn run(args: &[String]) -> Result<()> {
+    match args[0].as_str() {
+        "list" | "ls" => cmd_list(&args[1..]),
+        "show" => cmd_show(&args[1..]),
+        "connect" => cmd_connect(&args[1..]),
+        "add" => cmd_add(&args[1..]),
+        "edit" => cmd_edit(&args[1..]),
+        "rm" => cmd_rm(&args[1..]),
+        "pick" => pick::run(&args[1..]),
+        "tag" => cmd_tag(&args[1..]),
+        "import" => cmd_import(&args[1..]),
+        "export" => cmd_export(&args[1..]),
+        "completions" => completions::run(&args[1..]),
+        "__names" => completions::names(),
+        "--print" => cmd_connect(args),
+        "help" | "-h" | "--help" => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        other => bail!("unknown command '{}'\n\n{}", other, USAGE),
+    }
+}
+
+fn cmd_tag(args: &[String]) -> Result<()> {
+    let Some((name, edits)) = args.split_first() else {
+        bail!("Usage: jumpseat tag <name> [+tag|-tag]...");
+    };
+    let _lock = lock_config()?;
+    let mut conns = load_connections()?;
+    let i = find(&conns, name)?;
+    let conn = &mut conns[i];
+    if !edits.is_empty() {
+        conn.edit_tags(edits.iter().map(String::as_str));
+    }
+    println!("{}", conn.tags.join(" "));
+    if !edits.is_empty() {
+        save_connections(&conns)?;
+    }
+    Ok(())
+}
+
+/// Indices of connections matching `query` (TUI search syntax), ranked
+/// the way the TUI lists them.
+fn search(conns: &[Connection], query: &str) -> Vec<usize> {
+    let mut app = AppState {
+        connections: conns.to_vec(),
+        usage: load_usage().unwrap_or_default(),
+        search: query.to_string(),
+        ..Default::default()
+    };
+    app.apply_filter();
+    app.filtered_indices
+}
+
+/// The one connection `query` means: an exact name, or else the only search
+/// result. Fails on no match and lists the candidates when ambiguous.
+fn resolve(conns: &[Connection], query: &str) -> Result<usize> {
+    if let Some(i) = conns.iter().position(|c| c.name == query) {
+        return Ok(i);
+    }
+    let hits = search(conns, query);
+    match hits.as_slice() {
+        [] => bail!("no connection matches '{}'", query),
+        [i] => Ok(*i),
+        _ => {
+            let names: Vec<&str> = hits.iter().map(|&i| conns[i].name.as_str()).collect();
+            bail!(
+                "'{}' is ambiguous, it matches {} connections: {}",
+                query,
+                names.len(),
+                names.join(", ")
+            )
+        }
+    }
+}
+
+/// Index of the connection named exactly `name`.
+fn find(conns: &[Connection], name: &str) -> Result<usize> {
+    conns
+        .iter()
+        .position(|c| c.name == name)
+        .with_context(|| format!("no connection named '{}'", name))
+}
+
+/// Split `--json` off the other arguments.
+fn json_flag(args: &[String]) -> (bool, Vec<&str>) {
+    let json = args.iter().any(|a| a == "--json");
+    (json, args.iter().filter(|a| *a != "--json").map(String::as_str).collect())
+}
+
+fn cmd_list(args: &[String]) -> Result<()> {
+    let (json, query) = json_flag(args);
+    let conns = load_connections()?;
+    let hits: Vec<&Connection> = search(&conns, &query.join(" "))
+        .into_iter()
+        .map(|i| &conns[i])
+        .collect();
+    if json {
+        println!("{}", serde_json::to_string_pretty(&hits)?);
+        return Ok(());
+    }
+    let rows: Vec<[String; 4]> = hits
+        .iter()
+        .map(|c| {
+            let dest = match c.user.as_str() {
+                "" => c.host_port(),
+                user => format!("{}@{}", user, c.host_port()),
+            };
+            [c.name.clone(), dest, c.group_name(), c.tags.join(",")]
+        })
+        .collect();
+    let width = |col: usize| rows.iter().map(|r| r[col].chars().count()).max().unwrap_or(0);
+    let (w0, w1, w2) = (width(0), width(1), width(2));
+    for [name, dest, group, tags] in &rows {
+        let line = format!("{:<w0$}  {:<w1$}  {:<w2$}  {}", name, dest, group, tags);
+        println!("{}", line.trim_end());
+    }
+    Ok(())
+}
+
+fn cmd_show(args: &[String]) -> Result<()> {
+    let (json, query) = json_flag(args);
+    let [query] = query.as_slice() else {
+        bail!("Usage: jumpseat show [--json] <query>");
+    };
+    let conns = load_connections()?;
+    let conn = &conns[resolve(&conns, query)?];
+    if json {
+        println!("{}", serde_json::to_string_pretty(conn)?);
+        return Ok(());
+    }
+    let form = ConnectionForm::from_connection(conn, None);
+    for f in &form.fields {
+        if !f.value.is_empty() {
+            println!("{:>7}: {}", f.field.label(), f.value);
+        }
+    }
+    println!("      $ {}", command_preview(conn, &conns)?);
+    Ok(())
+}
+
+fn cmd_connect(args: &[String]) -> Result<()> {
+    let print = matches!(args.first().map(String::as_str), Some("--print"));
+    let args = if print { &args[1..] } else { args };
+    let Some((query, command)) = args.split_first() else {
+        bail!("Usage: jumpseat connect [--print] <query> [command...]");
+    };
+    let conns = load_connections()?;
+    let conn = &conns[resolve(&conns, query)?];
+    // Like ssh, the remaining words form the command
+    let command = match command {
+        [] => conn.remote_command.clone(),
+        words => Some(words.join(" ")),
+    };
+    if print {
+        // Printed to be pasted into a terminal, so with -t like connect there
+        println!("{}", SshCommand::build(conn, &conns, command.as_deref(), true)?.shell_line());
+        return Ok(());
+    }
+    let status = exec_ssh(conn, &conns, command.as_deref())?;
+    if !status.success() {
+        process::exit(status.code().unwrap_or(255));
+    }
+    Ok(())
+}
+
+/// Fill form fields from `--flag value` pairs. List flags may repeat and
+/// add up; other flags take their last value.
+fn apply_flags(form: &mut ConnectionForm, args: &[String]) -> Result<()> {
+    let mut values: Vec<(Field, String)> = Vec::new();
+    let mut it = args.iter();
+    while let Some(flag) = it.next() {
+        let Some(&(_, field)) = FIELD_FLAGS.iter().find(|(f, _)| f == flag) else {
+            bail!("unknown option '{}'\n\n{}", flag, USAGE);
+        };
+        let Some(value) = it.next() else {
+            bail!("{} needs a value", flag);
+        };
+        let separator = match field {
+            Field::Tags | Field::Jump | Field::Forwards => Some(", "),
+            Field::Env | Field::Options => Some("; "),
+            _ => None,
+        };
+        match (values.iter_mut().find(|(f, _)| *f == field), separator) {
+            (Some((_, v)), Some(sep)) => {
+                v.push_str(sep);
+                v.push_str(value);
+            }
+            (Some((_, v)), None) => *v = value.clone(),
+            (None, _) => values.push((field, value.clone())),
+        }
+    }
+    for (field, value) in values {
+        form.set_value(field, &value);
+    }
+    Ok(())
+}
+
+/// Validate the form like the TUI does on save, reporting every problem.
+fn check_form(form: &mut ConnectionForm, conns: &[Connection]) -> Result<()> {
+    form.touch_all();
+    if !form.validate(conns) {
+        let errors: Vec<String> = form
+            .fields
+            .iter()
+            .filter_map(|f| f.error.as_ref().map(|e| format!("  {}: {}", f.field.label(), e)))
+            .collect();
+        bail!("invalid connection:\n{}", errors.join("\n"));
+    }
+    if let Some(w) = form.first_warning() {
+        eprintln!("warning: {}", w);
+    }
+    Ok(())
+}
+
+fn cmd_add(args: &[String]) -> Result<()> {
+    let [name, host, flags @ ..] = args else {
+        bail!("Usage: jumpseat add <name> <host> [options]");
+    };
+    let _lock = lock_config()?;
+    let mut conns = load_connections()?;
+    let mut form = ConnectionForm::new();
+    form.set_value(Field::Name, name);
+    form.set_value(Field::Host, host);
+    apply_flags(&mut form, flags)?;
+    check_form(&mut form, &conns)?;
+    let conn = form.apply(&Default::default());
+    let name = conn.name.clone();
+    conns.push(conn);
+    save_connections(&conns)?;
+    println!("Added {}", name);
+    Ok(())
+}
+
+fn cmd_edit(args: &[String]) -> Result<()> {
+    let Some((name, flags)) = args.split_first() else {
+        bail!("Usage: jumpseat edit <name> [options]");
+    };
+    if flags.is_empty() {
+        bail!("nothing to change; see jumpseat help for the options");
+    }
+    let _lock = lock_config()?;
+    let mut conns = load_connections()?;
+    let i = find(&conns, name)?;
+    let mut form = ConnectionForm::from_connection(&conns[i], Some(i));
+    apply_flags(&mut form, flags)?;
+    check_form(&mut form, &conns)?;
+    let conn = form.apply(&conns[i]);
+    let old = std::mem::replace(&mut conns[i], conn);
+    let new = conns[i].name.clone();
+    let renamed = new != old.name;
+    let via = if renamed {
+        rename_jump_host(&mut conns, &old.name, &new)
+    } else {
+        0
+    };
+    save_connections(&conns)?;
+    if renamed {
+        rename_usage(&old.name, &new)?;
+    }
+    println!("Updated {}", new);
+    if via > 0 {
+        println!("Updated the jump hosts of {} other connection(s)", via);
+    }
+    Ok(())
+}
+
+fn cmd_rm(args: &[String]) -> Result<()> {
+    if args.is_empty() {
+        bail!("Usage: jumpseat rm <name>...");
+    }
+    let _lock = lock_config()?;
+    let mut conns = load_connections()?;
+    // Check every name before removing any
+    for name in args {
+        find(&conns, name)?;
+        let users: Vec<&str> = jump_dependents(&conns, name)
+            .into_iter()
+            .filter(|u| !args.iter().any(|a| a == u))
+            .collect();
+        if !users.is_empty() {
+            bail!(
+                "{} is the jump host of {}; change or remove those first",
+                name,
+                users.join(", ")
+            );
+        }
+    }
+    conns.retain(|c| !args.contains(&c.name));
+    save_connections(&conns)?;
+    for name in args {
+        println!("Removed {}", name);
+    }
+    Ok(())
+}
+
+fn cmd_import(args: &[String]) -> Result<()> {
+    let path = match args {
+        [] => default_ssh_config_path()?,
+        [p] => PathBuf::from(p),
+        _ => bail!("Usage: jumpseat import [path]"),
+    };
+    let _lock = lock_config()?;
+    let mut conns = load_connections()?;
+    let (incoming, warnings) = load_ssh_config(&path)?;
+    for w in &warnings {
+        eprintln!("warning: {}", w);
+    }
+    let report = merge_connections(&mut conns, incoming);
+    if !report.added.is_empty() {
+        save_connections(&conns)?;
+    }
+    for name in &report.added {
+        println!("added      {}", name);
+    }
+    for name in &report.skipped {
+        println!("skipped    {}", name);
+    }
+    for name in &report.conflicts {
+        println!("conflict   {}", name);
+    }
+    println!("{}: {}", path.display(), report.summary());
+    Ok(())
+}
+
+fn cmd_export(args: &[String]) -> Result<()> {
+    let conns = load_connections()?;
+    match args {
+        [] => {
+            print!("{}", render_ssh_config(&conns));
+        }
+        [flag, rest @ ..] if flag == "--write" => {
+            let path = match rest {
+                [] => default_ssh_config_path()?,
+                [p] => PathBuf::from(p),
+                _ => bail!("Usage: jumpseat export [--write [path]]"),
+            };
+            write_managed_section(&path, &conns)?;
+            println!("Wrote {} hosts to {}", conns.len(), path.display());
+        }
+        _ => bail!("Usage: jumpseat export [--write [path]]"),
+    }
+    Ok(())
+}
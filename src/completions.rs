@@ -0,0 +1,234 @@
+use anyhow::{bail, Result};
+
+use crate::cli::FIELD_FLAGS;
+use crate::config::load_connections;
+
+/// Subcommands offered for the first word, with a short description.
+const COMMANDS: &[(&str, &str)] = &[
+    ("list", "List connections"),
+    ("show", "Show one connection"),
+    ("connect", "Connect to a connection"),
+    ("add", "Add a connection"),
+    ("edit", "Change a connection"),
+    ("rm", "Remove connections"),
+    ("pick", "Choose a connection and print it"),
+    ("tag", "Show or edit tags"),
+    ("import", "Import hosts from an OpenSSH config"),
+    ("export", "Export connections as an OpenSSH config"),
+    ("completions", "Print a shell completion script"),
+];
+
+/// `jumpseat completions <bash|zsh|fish>`: print the completion script.
+pub fn run(args: &[String]) -> Result<()> {
+    let template = match args.first().map(String::as_str) {
+        Some("bash") => BASH,
+        Some("zsh") => ZSH,
+        Some("fish") => FISH,
+        _ => bail!("Usage: jumpseat completions <bash|zsh|fish>"),
+    };
+    print!("{}", render(template));
+    Ok(())
+}
+
+/// Hidden `jumpseat __names`: connection names, one per line, for the
+/// scripts to complete from.
+pub fn names() -> Result<()> {
+    for conn in load_connections()? {
+        println!("{}", conn.name);
+    }
+    Ok(())
+}
+
+fn render(template: &str) -> String {
+    let flags: Vec<&str> = FIELD_FLAGS.iter().map(|(flag, _)| *flag).collect();
+    let commands: Vec<&str> = COMMANDS.iter().map(|(name, _)| *name).collect();
+    let described: Vec<String> = COMMANDS
+        .iter()
+        .map(|(name, about)| format!("'{}:{}'", name, about))
+        .collect();
+    let fish_commands: Vec<String> = COMMANDS
+        .iter()
+        .map(|(name, about)| {
+            format!(
+                "complete -c jumpseat -n __fish_use_subcommand -a {} -d '{}'",
+                name, about
+            )
+        })
+        .collect();
+    let fish_flags: Vec<String> = flags
+        .iter()
+        .map(|flag| {
+            let name = &flag[2..];
+            let values = match *flag {
+                "--via" => "-xa '(__jumpseat_names)'",
+                "--client" => "-xa 'ssh mosh et'",
+                "--key" => "-r -F",
+                _ => "-x",
+            };
+            format!(
+                "complete -c jumpseat -n '__fish_seen_subcommand_from add edit' -l {} {}",
+                name, values
+            )
+        })
+        .collect();
+    template
+        .replace("@COMMANDS@", &commands.join(" "))
+        .replace("@DESCRIBED@", &described.join(" "))
+        .replace("@FLAGS@", &flags.join(" "))
+        .replace("@FISH_COMMANDS@", &fish_commands.join("\n"))
+        .replace("@FISH_FLAGS@", &fish_flags.join("\n"))
+}
+
+const BASH: &str = r#"# jumpseat completion for bash: source <(jumpseat completions bash)
+_jumpseat_names() {
+    # One name per line; names may contain spaces
+    local IFS=$'\n'
+    COMPREPLY=($(compgen -W "$("${COMP_WORDS[0]}" __names 2>/dev/null)" -- "$cur"))
+}
+
+_jumpseat() {
+    local cur=${COMP_WORDS[COMP_CWORD]} prev=${COMP_WORDS[COMP_CWORD-1]}
+    local cmd=${COMP_WORDS[1]}
+
+    if [[ $COMP_CWORD -eq 1 ]]; then
+        COMPREPLY=($(compgen -W "@COMMANDS@ --print" -- "$cur"))
+        return
+    fi
+    case $prev in
+        --via) _jumpseat_names; return ;;
+        --key) COMPREPLY=($(compgen -f -- "$cur")); return ;;
+        --client) COMPREPLY=($(compgen -W "ssh mosh et" -- "$cur")); return ;;
+        -f|--format) COMPREPLY=($(compgen -W "name dest argv" -- "$cur")); return ;;
+    esac
+    case " @FLAGS@ " in
+        *" $prev "*) return ;;
+    esac
+
+    case $cmd in
+        connect|--print)
+            # Only the first word after the command (and --print) is a name
+            if [[ $prev == connect || $prev == --print ]]; then
+                if [[ $cur == -* ]]; then
+                    COMPREPLY=($(compgen -W "--print" -- "$cur"))
+                else
+                    _jumpseat_names
+                fi
+            fi
+            ;;
+        show)
+            if [[ $cur == -* ]]; then
+                COMPREPLY=($(compgen -W "--json" -- "$cur"))
+            else
+                _jumpseat_names
+            fi
+            ;;
+        rm) _jumpseat_names ;;
+        tag) [[ $COMP_CWORD -eq 2 ]] && _jumpseat_names ;;
+        edit)
+            if [[ $COMP_CWORD -eq 2 ]]; then
+                _jumpseat_names
+            else
+                COMPREPLY=($(compgen -W "@FLAGS@" -- "$cur"))
+            fi
+            ;;
+        add) [[ $COMP_CWORD -gt 3 ]] && COMPREPLY=($(compgen -W "@FLAGS@" -- "$cur")) ;;
+        list) COMPREPLY=($(compgen -W "--json" -- "$cur")) ;;
+        pick) COMPREPLY=($(compgen -W "--format" -- "$cur")) ;;
+        import) COMPREPLY=($(compgen -f -- "$cur")) ;;
+        export)
+            if [[ $cur == -* ]]; then
+                COMPREPLY=($(compgen -W "--write" -- "$cur"))
+            else
+                COMPREPLY=($(compgen -f -- "$cur"))
+            fi
+            ;;
+        completions) COMPREPLY=($(compgen -W "bash zsh fish" -- "$cur")) ;;
+    esac
+}
+complete -o default -F _jumpseat jumpseat
+"#;
+
+const ZSH: &str = r#"#compdef jumpseat
+# jumpseat completion for zsh: source <(jumpseat completions zsh)
+_jumpseat() {
+    local cmd=$words[2] prev=$words[CURRENT-1]
+    local -a names commands
+    names=(${(f)"$($words[1] __names 2>/dev/null)"})
+    commands=(@DESCRIBED@)
+
+    if (( CURRENT == 2 )); then
+        _describe command commands
+        return
+    fi
+    case $prev in
+        --via) compadd -a names; return ;;
+        --key) _files; return ;;
+        --client) compadd ssh mosh et; return ;;
+        -f|--format) compadd name dest argv; return ;;
+    esac
+    (( ${${(z):-@FLAGS@}[(Ie)$prev]} )) && return
+
+    case $cmd in
+        connect|--print)
+            # Only the first word after the command (and --print) is a name
+            if [[ $prev == connect || $prev == --print ]]; then
+                compadd -a names
+                [[ $cmd == connect ]] && compadd -- --print
+            fi
+            ;;
+        show) compadd -a names; compadd -- --json ;;
+        rm) compadd -a names ;;
+        tag) (( CURRENT == 3 )) && compadd -a names ;;
+        edit)
+            if (( CURRENT == 3 )); then
+                compadd -a names
+            else
+                compadd -- @FLAGS@
+            fi
+            ;;
+        add) (( CURRENT > 4 )) && compadd -- @FLAGS@ ;;
+        list) compadd -- --json ;;
+        pick) compadd -- --format ;;
+        import) _files ;;
+        export) compadd -- --write; _files ;;
+        completions) compadd bash zsh fish ;;
+    esac
+}
+if [[ $zsh_eval_context[-1] == loadautofunc ]]; then
+    _jumpseat "$@"
+else
+    compdef _jumpseat jumpseat
+fi
+"#;
+
+const FISH: &str = r#"# jumpseat completion for fish: jumpseat completions fish | source
+function __jumpseat_names
+    jumpseat __names 2>/dev/null
+end
+
+# True when the word being completed is the connection name: the first
+# argument of the command (after connect's --print).
+function __jumpseat_wants_name
+    set -l words (commandline -opc)
+    switch (count $words)
+        case 2
+            contains -- $words[2] connect show edit rm tag --print
+        case 3
+            test "$words[2] $words[3]" = "connect --print"
+            or contains -- $words[2] show rm
+        case '*'
+            contains -- $words[2] show rm
+    end
+end
+
+complete -c jumpseat -f
+@FISH_COMMANDS@
+complete -c jumpseat -n __jumpseat_wants_name -a '(__jumpseat_names)'
+complete -c jumpseat -n '__fish_seen_subcommand_from connect' -l print
+complete -c jumpseat -n '__fish_seen_subcommand_from list show' -l json
+complete -c jumpseat -n '__fish_seen_subcommand_from pick' -s f -l format -xa 'name dest argv'
+complete -c jumpseat -n '__fish_seen_subcommand_from export' -l write
+complete -c jumpseat -n '__fish_seen_subcommand_from import export' -F
+complete -c jumpseat -n '__fish_seen_subcommand_from completions' -xa 'bash zsh fish'
+@FISH_FLAGS@
+"#;
@@ -1,66 +1,643 @@
 use anyhow::{bail, Context, Result};
-use directories::ProjectDirs;
-use std::{fs, path::PathBuf};
+use directories::{BaseDirs, ProjectDirs};
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    hash::{DefaultHasher, Hash, Hasher},
+    io::Write,
+    path::{Path, PathBuf},
+    process,
+    time::SystemTime,
+};
 
-use crate::models::Connection;
+use crate::models::{Connection, Transport, Usage};
 
-pub fn config_path() -> Result<PathBuf> {
+fn config_dir() -> Result<PathBuf> {
     let proj = ProjectDirs::from("dev", "minimal", "rssh")
         .ok_or_else(|| anyhow::anyhow!("cannot resolve config dir"))?;
     let dir = proj.config_dir().to_path_buf();
     fs::create_dir_all(&dir)?;
-    Ok(dir.join("connections.json"))
+    Ok(dir)
 }
 
-pub fn load_connections() -> Result<Vec<Connection>> {
+pub fn config_path() -> Result<PathBuf> {
+    Ok(config_dir()?.join("connections.json"))
+}
+
+/// Usage history lives apart from the connections so that syncing or
+/// hand-editing connections.json does not churn on every connect.
+pub fn usage_path() -> Result<PathBuf> {
+    Ok(config_dir()?.join("usage.json"))
+}
+
+pub fn home_dir() -> Result<PathBuf> {
+    let dirs = BaseDirs::new().ok_or_else(|| anyhow::anyhow!("cannot resolve home dir"))?;
+    Ok(dirs.home_dir().to_path_buf())
+}
+
+/// Expand a leading `~/` (or a lone `~`) to the user's home directory.
+pub fn expand_tilde(path: &str) -> PathBuf {
+    let rest = match path.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with('/') => rest.trim_start_matches('/'),
+        _ => return PathBuf::from(path),
+    };
+    match home_dir() {
+        Ok(home) => home.join(rest),
+        Err(_) => PathBuf::from(path),
+    }
+}
+
+/// Exclusive advisory lock on connections.json, released when dropped.
+/// Hold it from load to save so that two writers cannot interleave. Reads
+/// alone need no lock, since saves replace the file atomically.
+pub struct ConfigLock {
+    _file: File,
+}
+
+pub fn lock_config() -> Result<ConfigLock> {
+    lock_file("connections.lock")
+}
+
+/// Lock for read-modify-write of usage.json. It is separate from
+/// `lock_config` so that a command holding that one can still record usage.
+fn lock_usage() -> Result<ConfigLock> {
+    lock_file("usage.lock")
+}
+
+fn lock_file(name: &str) -> Result<ConfigLock> {
+    // Saves rename a new file into place, so the lock lives on a file of its own
+    let path = config_dir()?.join(name);
+    let file = File::create(&path).with_context(|| format!("cannot open {}", path.display()))?;
+    file.lock()
+        .with_context(|| format!("cannot lock {}", path.display()))?;
+    Ok(ConfigLock { _file: file })
+}
+
+/// Fingerprint of connections.json's contents, to tell whether someone else
+/// has written it.
+#[derive(Clone, Copy, Default, PartialEq)]
+struct Stamp(u64);
+
+impl Stamp {
+    fn of(data: &str) -> Stamp {
+        let mut h = DefaultHasher::new();
+        data.hash(&mut h);
+        Stamp(h.finish())
+    }
+}
+
+/// Modification time of connections.json, None if it does not exist.
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// connections.json as this process last loaded or saved it. The TUI keeps
+/// one so that saving can merge with changes made meanwhile by another
+/// instance, the CLI or an editor, instead of overwriting them.
+#[derive(Default)]
+pub struct Snapshot {
+    pub connections: Vec<Connection>,
+    stamp: Stamp,
+    /// Only a hint for `refresh_connections`; `stamp` decides
+    modified: Option<SystemTime>,
+}
+
+pub fn load_snapshot() -> Result<Snapshot> {
     let path = config_path()?;
+    // Taken before reading, so a write racing with the read is seen next time
+    let modified = modified_time(&path);
     if !path.exists() {
-        Ok(vec![])
-    } else {
-        let data = fs::read_to_string(path)?;
-        let v: Vec<Connection> = serde_json::from_str(&data)?;
-        Ok(v)
+        return Ok(Snapshot::default());
+    }
+    let data = fs::read_to_string(&path)?;
+    let mut connections: Vec<Connection> = serde_json::from_str(&data)
+        .with_context(|| format!("cannot parse {}", path.display()))?;
+    connections.iter_mut().for_each(Connection::migrate);
+    Ok(Snapshot {
+        connections,
+        stamp: Stamp::of(&data),
+        modified,
+    })
+}
+
+pub fn load_connections() -> Result<Vec<Connection>> {
+    Ok(load_snapshot()?.connections)
+}
+
+/// Pick up changes written to disk since `base`, merging them into `ours`,
+/// which differs from `base` only if a save failed. Cheap when the file's
+/// mtime has not moved. Returns whether the file had changed; a file that
+/// cannot be read is reported once, not again until it is written.
+pub fn refresh_connections(base: &mut Snapshot, ours: &mut Vec<Connection>) -> Result<bool> {
+    let modified = modified_time(&config_path()?);
+    if modified == base.modified {
+        return Ok(false);
+    }
+    let theirs = match load_snapshot() {
+        Ok(theirs) => theirs,
+        Err(e) => {
+            base.modified = modified;
+            return Err(e);
+        }
+    };
+    if theirs.stamp == base.stamp {
+        base.modified = theirs.modified;
+        return Ok(false);
     }
+    *ours = merge_changes(&base.connections, ours, &theirs.connections).0;
+    *base = theirs;
+    Ok(true)
 }
 
+/// Outcome of `sync_connections`.
+#[derive(Default)]
+pub struct SyncReport {
+    /// The file had changed on disk and was merged into ours
+    pub merged: bool,
+    /// Connections changed both here and on disk; ours was kept unless we
+    /// deleted it
+    pub conflicts: Vec<String>,
+}
+
+/// Save `ours`, which was edited starting from `base`. If the file changed
+/// on disk since, the two sets of changes are merged by connection name and
+/// `ours` becomes the merged list. `base` is then updated to what was saved.
+pub fn sync_connections(base: &mut Snapshot, ours: &mut Vec<Connection>) -> Result<SyncReport> {
+    let _lock = lock_config()?;
+    let mut report = SyncReport::default();
+    let theirs = load_snapshot()?;
+    if theirs.stamp != base.stamp {
+        let (merged, conflicts) = merge_changes(&base.connections, ours, &theirs.connections);
+        *ours = merged;
+        report = SyncReport { merged: true, conflicts };
+    }
+    let stamp = write_connections(ours)?;
+    *base = Snapshot {
+        connections: ours.clone(),
+        stamp,
+        modified: modified_time(&config_path()?),
+    };
+    Ok(report)
+}
+
+/// Three-way merge of connection lists by name. Edits on only one side are
+/// taken as they are. When both sides changed the same connection, ours
+/// wins, except that a connection edited on disk survives our deleting it.
+/// Returns the merged list, in disk order with our additions at the end,
+/// and the names that conflicted.
+fn merge_changes(
+    base: &[Connection],
+    ours: &[Connection],
+    theirs: &[Connection],
+) -> (Vec<Connection>, Vec<String>) {
+    let find = |list: &'_ [Connection], name: &str| list.iter().position(|c| c.name == name);
+    let mut merged = Vec::new();
+    let mut conflicts = Vec::new();
+
+    for t in theirs {
+        let b = find(base, &t.name).map(|i| &base[i]);
+        let o = find(ours, &t.name).map(|i| &ours[i]);
+        match (b, o) {
+            // Untouched here, or added only on disk
+            (Some(b), Some(o)) if o == b => merged.push(t.clone()),
+            (None, None) => merged.push(t.clone()),
+            // Edited here, or added on both sides
+            (b, Some(o)) => {
+                if t != o && b != Some(t) {
+                    conflicts.push(t.name.clone());
+                }
+                merged.push(o.clone());
+            }
+            // Deleted here
+            (Some(b), None) => {
+                if t != b {
+                    conflicts.push(t.name.clone());
+                    merged.push(t.clone());
+                }
+            }
+        }
+    }
+    for o in ours {
+        if find(theirs, &o.name).is_some() {
+            continue;
+        }
+        match find(base, &o.name).map(|i| &base[i]) {
+            // Added here
+            None => merged.push(o.clone()),
+            // Deleted on disk and untouched here
+            Some(b) if b == o => {}
+            // Deleted on disk but edited here
+            Some(_) => {
+                conflicts.push(o.name.clone());
+                merged.push(o.clone());
+            }
+        }
+    }
+    (merged, conflicts)
+}
+
+/// How many previous versions of connections.json are kept, as
+/// `connections.json.bak.1` (newest) to `.bak.N`.
+const BACKUPS: usize = 3;
+
+/// Overwrite connections.json. Callers that loaded it first should hold
+/// `lock_config` across both.
 pub fn save_connections(conns: &[Connection]) -> Result<()> {
+    write_connections(conns).map(|_| ())
+}
+
+fn write_connections(conns: &[Connection]) -> Result<Stamp> {
     let path = config_path()?;
     let data = serde_json::to_string_pretty(conns)?;
-    fs::write(path, data)?;
+    if path.exists() {
+        rotate_backups(&path)
+            .with_context(|| format!("cannot back up {}", path.display()))?;
+    }
+    write_atomic(&path, data.as_bytes())?;
+    Ok(Stamp::of(&data))
+}
+
+fn backup_path(path: &Path, n: usize) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(format!(".bak.{}", n));
+    PathBuf::from(name)
+}
+
+/// Shift `.bak.1..N-1` up by one, dropping the oldest, and copy `path` to
+/// `.bak.1`.
+fn rotate_backups(path: &Path) -> Result<()> {
+    for n in (1..BACKUPS).rev() {
+        let from = backup_path(path, n);
+        if from.exists() {
+            fs::rename(&from, backup_path(path, n + 1))?;
+        }
+    }
+    fs::copy(path, backup_path(path, 1))?;
+    Ok(())
+}
+
+/// Replace `path` with `data` so that it is either fully the old or fully
+/// the new contents, even if we crash or the disk fills up: write a temp
+/// file next to it, fsync, then rename it over the original.
+pub fn write_atomic(path: &Path, data: &[u8]) -> Result<()> {
+    // Replace the target of a symlink (say, into a dotfiles repo), not the link
+    let path = &fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let dir = path
+        .parent()
+        .with_context(|| format!("{} has no parent directory", path.display()))?;
+    let mut tmp_name = path
+        .file_name()
+        .with_context(|| format!("{} has no file name", path.display()))?
+        .to_owned();
+    tmp_name.push(format!(".{}.tmp", process::id()));
+    let tmp = dir.join(tmp_name);
+
+    // The new file takes the place of the old one, so it keeps its mode
+    // (e.g. 600) rather than getting the umask default
+    let mode = fs::metadata(path).map(|m| m.permissions()).ok();
+    let written = File::create(&tmp)
+        .and_then(|mut f| {
+            if let Some(mode) = mode {
+                f.set_permissions(mode)?;
+            }
+            f.write_all(data)?;
+            f.sync_all()
+        })
+        .and_then(|()| fs::rename(&tmp, path));
+    if let Err(e) = written {
+        let _ = fs::remove_file(&tmp);
+        return Err(e).with_context(|| format!("cannot write {}", path.display()));
+    }
+    // Make the rename itself durable
+    if let Ok(d) = File::open(dir) {
+        let _ = d.sync_all();
+    }
     Ok(())
 }
 
-pub fn add_from_line(line: &str) -> Result<Connection> {
-    // Format:  "<name> <user>@<host>[:port] [term]"
-    // Minimal & forgiving: split by whitespace
-    let parts: Vec<&str> = line.split_whitespace().collect();
-    if parts.len() < 2 {
-        bail!("Add format: <name> <user>@<host>[:port] [term]");
+/// Usage per connection name.
+pub fn load_usage() -> Result<HashMap<String, Usage>> {
+    let path = usage_path()?;
+    if !path.exists() {
+        return Ok(HashMap::new());
     }
-    let name = parts[0].to_string();
-    let uh = parts[1];
+    let data = fs::read_to_string(path)?;
+    Ok(serde_json::from_str(&data)?)
+}
 
-    let (user, host_port) = uh
-        .split_once('@')
-        .context("must include user@host")?;
-    let (host, port) = if let Some((h, p)) = host_port.split_once(':') {
-        let port: u16 = p.parse().context("invalid port")?;
-        (h.to_string(), Some(port))
-    } else {
-        (host_port.to_string(), None)
+pub fn save_usage(usage: &HashMap<String, Usage>) -> Result<()> {
+    let path = usage_path()?;
+    let data = serde_json::to_string_pretty(usage)?;
+    write_atomic(&path, data.as_bytes())
+}
+
+/// Count a connect to `name` now.
+pub fn record_usage(name: &str) -> Result<()> {
+    let _lock = lock_usage()?;
+    let mut usage = load_usage()?;
+    usage.entry(name.to_string()).or_default().record();
+    save_usage(&usage)
+}
+
+/// Carry usage history over when a connection is renamed.
+pub fn rename_usage(old: &str, new: &str) -> Result<()> {
+    let _lock = lock_usage()?;
+    let mut usage = load_usage()?;
+    if let Some(u) = usage.remove(old) {
+        usage.insert(new.to_string(), u);
+        save_usage(&usage)?;
+    }
+    Ok(())
+}
+
+#[derive(Default)]
+pub struct ImportReport {
+    pub added: Vec<String>,
+    /// Already present with identical settings
+    pub skipped: Vec<String>,
+    /// Name already taken by a connection with different settings; kept as is
+    pub conflicts: Vec<String>,
+}
+
+impl ImportReport {
+    pub fn summary(&self) -> String {
+        let mut s = format!(
+            "{} added, {} skipped, {} conflicting",
+            self.added.len(),
+            self.skipped.len(),
+            self.conflicts.len()
+        );
+        if !self.conflicts.is_empty() {
+            s.push_str(&format!(" ({})", self.conflicts.join(", ")));
+        }
+        s
+    }
+}
+
+/// Merge `incoming`, read from an OpenSSH config, into `existing` by name.
+/// Existing entries always win.
+pub fn merge_connections(existing: &mut Vec<Connection>, incoming: Vec<Connection>) -> ImportReport {
+    let mut report = ImportReport::default();
+    for conn in incoming {
+        match existing.iter().find(|c| c.name == conn.name) {
+            Some(c) if same_ssh_settings(c, &conn) => report.skipped.push(conn.name),
+            Some(_) => report.conflicts.push(conn.name),
+            None => {
+                report.added.push(conn.name.clone());
+                existing.push(conn);
+            }
+        }
+    }
+    report
+}
+
+/// Whether two connections agree on everything an OpenSSH config holds.
+/// Group, tags, client and `TERM` exist only in jumpseat, so an exported
+/// host that is imported again still matches.
+fn same_ssh_settings(a: &Connection, b: &Connection) -> bool {
+    let ssh_only = |c: &Connection| {
+        let mut c = c.clone();
+        c.group = None;
+        c.tags.clear();
+        c.transport = Transport::Ssh;
+        c.set_term(None);
+        c
     };
+    ssh_only(a) == ssh_only(b)
+}
 
-    let term = if parts.len() >= 3 {
-        Some(parts[2].to_string())
+#[derive(Debug, PartialEq)]
+pub struct HostSpec {
+    /// Empty when the spec has no `user@`
+    pub user: String,
+    pub host: String,
+    pub port: Option<u16>,
+}
+
+/// Parse a destination as typed by a user. Accepted forms:
+///
+/// - `host`, `user@host`, `user@host:port`
+/// - `[v6addr]`, `user@[v6addr]:port` (brackets also work for names)
+/// - a bare IPv6 literal such as `2001:db8::1` (no port possible)
+/// - `ssh://[user@]host[:port]`
+pub fn parse_host_spec(spec: &str) -> Result<HostSpec> {
+    let spec = spec.trim();
+    let rest = match spec.strip_prefix("ssh://") {
+        Some(rest) => rest.strip_suffix('/').unwrap_or(rest),
+        None => spec,
+    };
+    if rest.contains('/') {
+        bail!("unexpected '/' in '{}'", spec);
+    }
+
+    let (user, host_port) = match rest.rsplit_once('@') {
+        Some(("", _)) => bail!("missing user before '@'"),
+        Some((u, hp)) => (u, hp),
+        None => ("", rest),
+    };
+
+    let (host, port) = if let Some(inner) = host_port.strip_prefix('[') {
+        let (host, after) = inner
+            .split_once(']')
+            .context("missing ']' after bracketed host")?;
+        let port = match after {
+            "" => None,
+            p => Some(parse_port(
+                p.strip_prefix(':')
+                    .with_context(|| format!("unexpected '{}' after ']'", p))?,
+            )?),
+        };
+        (host, port)
+    } else if host_port.matches(':').count() > 1 {
+        // More than one colon can only be a bare IPv6 address
+        let addr = host_port.split('%').next().unwrap_or_default();
+        if addr.parse::<std::net::Ipv6Addr>().is_err() {
+            bail!(
+                "'{}' is not a valid IPv6 address (write [addr]:port to add a port)",
+                host_port
+            );
+        }
+        (host_port, None)
+    } else if let Some((h, p)) = host_port.split_once(':') {
+        (h, Some(parse_port(p)?))
     } else {
-        None
+        (host_port, None)
     };
 
-    Ok(Connection {
-        name,
+    if host.is_empty() {
+        bail!("missing host");
+    }
+    if host.contains(char::is_whitespace) {
+        bail!("host cannot contain spaces");
+    }
+    Ok(HostSpec {
         user: user.to_string(),
-        host,
+        host: host.to_string(),
         port,
-        term,
     })
-}
\ No newline at end of file
+}
+
+fn parse_port(p: &str) -> Result<u16> {
+    match p.parse::<u16>() {
+        Ok(port) if port > 0 => Ok(port),
+        _ if p.is_empty() => bail!("missing port after ':'"),
+        _ => bail!("invalid port '{}' (expected 1-65535)", p),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spec(user: &str, host: &str, port: Option<u16>) -> HostSpec {
+        HostSpec {
+            user: user.to_string(),
+            host: host.to_string(),
+            port,
+        }
+    }
+
+    #[test]
+    fn host_spec_forms() {
+        let cases = [
+            ("host", spec("", "host", None)),
+            ("user@host", spec("user", "host", None)),
+            ("user@host:2222", spec("user", "host", Some(2222))),
+            ("  host:22  ", spec("", "host", Some(22))),
+            ("[2001:db8::1]:2222", spec("", "2001:db8::1", Some(2222))),
+            ("alice@[::1]", spec("alice", "::1", None)),
+            ("[web]:2200", spec("", "web", Some(2200))),
+            ("2001:db8::1", spec("", "2001:db8::1", None)),
+            ("fe80::1%eth0", spec("", "fe80::1%eth0", None)),
+            ("ssh://deploy@example.com:2200/", spec("deploy", "example.com", Some(2200))),
+            ("ssh://example.com", spec("", "example.com", None)),
+            // Only the last @ separates the user
+            ("me@corp@host", spec("me@corp", "host", None)),
+        ];
+        for (input, want) in cases {
+            assert_eq!(parse_host_spec(input).unwrap(), want, "{}", input);
+        }
+    }
+
+    #[test]
+    fn host_spec_errors() {
+        let cases = [
+            ("[::1:22", "missing ']'"),
+            ("[::1]x", "unexpected 'x' after ']'"),
+            ("host:0", "invalid port '0'"),
+            ("host:65536", "invalid port '65536'"),
+            ("host:", "missing port"),
+            ("@host", "missing user"),
+            ("ssh://host/path", "unexpected '/'"),
+            ("user@host/x", "unexpected '/'"),
+            ("2001:db8::zz", "not a valid IPv6 address"),
+            ("user@", "missing host"),
+            ("", "missing host"),
+        ];
+        for (input, want) in cases {
+            let err = parse_host_spec(input).unwrap_err().to_string();
+            assert!(err.contains(want), "{}: got '{}'", input, err);
+        }
+    }
+
+    fn conn(name: &str, port: Option<u16>) -> Connection {
+        let mut c = Connection::default();
+        c.name = name.to_string();
+        c.host = format!("{}.example", name);
+        c.port = port;
+        c
+    }
+
+    /// Merge and return the names and ports of the result, and the conflicts.
+    fn merge(
+        base: &[Connection],
+        ours: &[Connection],
+        theirs: &[Connection],
+    ) -> (Vec<(String, Option<u16>)>, Vec<String>) {
+        let (merged, conflicts) = merge_changes(base, ours, theirs);
+        (merged.into_iter().map(|c| (c.name, c.port)).collect(), conflicts)
+    }
+
+    fn names(items: &[(&str, Option<u16>)]) -> Vec<(String, Option<u16>)> {
+        items.iter().map(|(n, p)| (n.to_string(), *p)).collect()
+    }
+
+    #[test]
+    fn merge_one_sided_changes() {
+        let base = [conn("a", None), conn("b", None)];
+        // Only the disk changed: take it as is
+        let theirs = [conn("a", Some(1)), conn("c", None)];
+        assert_eq!(merge(&base, &base, &theirs), (names(&[("a", Some(1)), ("c", None)]), vec![]));
+        // Only we changed: keep ours
+        let ours = [conn("b", Some(2)), conn("d", None)];
+        assert_eq!(merge(&base, &ours, &base), (names(&[("b", Some(2)), ("d", None)]), vec![]));
+    }
+
+    #[test]
+    fn merge_add_add() {
+        let base = [conn("a", None)];
+        let ours = [conn("a", None), conn("b", None)];
+        let theirs = [conn("a", None), conn("c", None)];
+        assert_eq!(
+            merge(&base, &ours, &theirs),
+            (names(&[("a", None), ("c", None), ("b", None)]), vec![])
+        );
+
+        // Same name added on both sides: ours wins, unless they agree
+        let ours = [conn("a", None), conn("b", Some(1))];
+        let theirs = [conn("a", None), conn("b", Some(2))];
+        assert_eq!(
+            merge(&base, &ours, &theirs),
+            (names(&[("a", None), ("b", Some(1))]), vec!["b".to_string()])
+        );
+        assert_eq!(merge(&base, &ours, &ours), (names(&[("a", None), ("b", Some(1))]), vec![]));
+    }
+
+    #[test]
+    fn merge_edit_edit() {
+        let base = [conn("a", None)];
+        let ours = [conn("a", Some(1))];
+        let theirs = [conn("a", Some(2))];
+        assert_eq!(merge(&base, &ours, &theirs), (names(&[("a", Some(1))]), vec!["a".to_string()]));
+        // The same edit on both sides is no conflict
+        assert_eq!(merge(&base, &ours, &ours), (names(&[("a", Some(1))]), vec![]));
+    }
+
+    #[test]
+    fn merge_delete_vs_edit() {
+        let base = [conn("a", None), conn("b", None)];
+        // We deleted a, they edited it: their edit survives
+        let ours = [conn("b", None)];
+        let theirs = [conn("a", Some(1)), conn("b", None)];
+        assert_eq!(
+            merge(&base, &ours, &theirs),
+            (names(&[("a", Some(1)), ("b", None)]), vec!["a".to_string()])
+        );
+        // We deleted a, they left it alone: it goes
+        assert_eq!(merge(&base, &ours, &base), (names(&[("b", None)]), vec![]));
+
+        // They deleted a, we edited it: our edit survives
+        let ours = [conn("a", Some(1)), conn("b", None)];
+        let theirs = [conn("b", None)];
+        assert_eq!(
+            merge(&base, &ours, &theirs),
+            (names(&[("b", None), ("a", Some(1))]), vec!["a".to_string()])
+        );
+        // They deleted a, we left it alone: it goes
+        assert_eq!(merge(&base, &base, &theirs), (names(&[("b", None)]), vec![]));
+    }
+
+    #[test]
+    fn merge_keeps_disk_order() {
+        let base = [conn("a", None), conn("b", None), conn("c", None)];
+        let ours = [conn("a", None), conn("b", Some(1)), conn("c", None), conn("new", None)];
+        let theirs = [conn("c", None), conn("a", None), conn("b", None)];
+        assert_eq!(
+            merge(&base, &ours, &theirs),
+            (
+                names(&[("c", None), ("a", None), ("b", Some(1)), ("new", None)]),
+                vec![]
+            )
+        );
+    }
+}
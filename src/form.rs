@@ -0,0 +1,384 @@
+use crate::config::{expand_tilde, parse_host_spec, HostSpec};
+use crate::models::{jump_chain, Connection, Forward, Transport};
+use crate::ssh_options::{canonical_name, parse_env, parse_option};
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Field {
+    Name,
+    Group,
+    User,
+    Host,
+    Port,
+    Term,
+    Env,
+    Tags,
+    Identity,
+    Jump,
+    Transport,
+    Forwards,
+    Command,
+    Options,
+}
+
+impl Field {
+    pub const ALL: [Field; 14] = [
+        Field::Name,
+        Field::Group,
+        Field::User,
+        Field::Host,
+        Field::Port,
+        Field::Term,
+        Field::Env,
+        Field::Tags,
+        Field::Identity,
+        Field::Jump,
+        Field::Transport,
+        Field::Forwards,
+        Field::Command,
+        Field::Options,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Field::Name => "Name",
+            Field::Group => "Group",
+            Field::User => "User",
+            Field::Host => "Host",
+            Field::Port => "Port",
+            Field::Term => "Term",
+            Field::Env => "Env",
+            Field::Tags => "Tags",
+            Field::Identity => "Key",
+            Field::Jump => "Via",
+            Field::Transport => "Client",
+            Field::Forwards => "Fwd",
+            Field::Command => "Cmd",
+            Field::Options => "Opts",
+        }
+    }
+
+    /// Shown dimmed while the field is empty; describes what an empty value means.
+    pub fn placeholder(self) -> &'static str {
+        match self {
+            Field::Name => "same as host",
+            Field::Group => "none, or a path like prod/eu/db",
+            Field::User => "local user",
+            Field::Host => "host, user@host:port, [v6]:port or ssh://…",
+            Field::Port => "22",
+            Field::Term => "xterm-256color",
+            Field::Env => "none, e.g. LANG=en_US.UTF-8; LC_ALL=C",
+            Field::Tags => "comma or space separated, e.g. prod, web",
+            Field::Identity => "ssh default (Ctrl-K to pick from ~/.ssh)",
+            Field::Jump => "direct, or jump host names: bastion, inner (Ctrl-K to pick)",
+            Field::Transport => "ssh, mosh, et, or an ssh-compatible binary",
+            Field::Forwards => "none, e.g. L 8080:localhost:80, R 9000:localhost:3000, D 1080",
+            Field::Command => "login shell, or run e.g. tmux new -A -s main",
+            Field::Options => "none, e.g. ServerAliveInterval=30; StrictHostKeyChecking=no",
+        }
+    }
+}
+
+pub struct FormField {
+    pub field: Field,
+    pub value: String,
+    pub error: Option<String>,
+    /// Shown like an error but does not block saving
+    pub warning: Option<String>,
+    /// Errors are only shown once a field has been typed in (or on save)
+    pub touched: bool,
+}
+
+/// Editable copy of a connection, one text field per setting.
+#[derive(Default)]
+pub struct ConnectionForm {
+    pub fields: Vec<FormField>,
+    pub focus: usize,
+    /// Index into `AppState::connections` being edited, None for a new one
+    pub editing: Option<usize>,
+}
+
+impl ConnectionForm {
+    pub fn new() -> Self {
+        Self::from_connection(&Connection::default(), None)
+    }
+
+    pub fn from_connection(conn: &Connection, editing: Option<usize>) -> Self {
+        let fields = Field::ALL
+            .iter()
+            .map(|&field| {
+                let value = match field {
+                    Field::Name => conn.name.clone(),
+                    Field::Group => conn.group_name(),
+                    Field::User => conn.user.clone(),
+                    Field::Host => conn.host.clone(),
+                    Field::Port => conn.port.map(|p| p.to_string()).unwrap_or_default(),
+                    Field::Term => conn.term().unwrap_or_default().to_string(),
+                    Field::Env => conn
+                        .env
+                        .iter()
+                        .filter(|(k, _)| *k != "TERM")
+                        .map(|(k, v)| format!("{}={}", k, v))
+                        .collect::<Vec<_>>()
+                        .join("; "),
+                    Field::Tags => conn.tags.join(", "),
+                    Field::Identity => conn.identity_file.clone().unwrap_or_default(),
+                    Field::Jump => conn.jump_hosts.join(", "),
+                    Field::Transport => match conn.transport {
+                        Transport::Ssh => String::new(),
+                        ref t => t.to_string(),
+                    },
+                    Field::Forwards => conn
+                        .forwards
+                        .iter()
+                        .map(Forward::to_string)
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                    Field::Command => conn.remote_command.clone().unwrap_or_default(),
+                    Field::Options => conn
+                        .options
+                        .iter()
+                        .map(|(k, v)| format!("{}={}", k, v))
+                        .collect::<Vec<_>>()
+                        .join("; "),
+                };
+                FormField {
+                    field,
+                    value,
+                    error: None,
+                    warning: None,
+                    touched: false,
+                }
+            })
+            .collect();
+        ConnectionForm {
+            fields,
+            focus: 0,
+            editing,
+        }
+    }
+
+    pub fn value(&self, field: Field) -> &str {
+        self.fields
+            .iter()
+            .find(|f| f.field == field)
+            .map(|f| f.value.trim())
+            .unwrap_or_default()
+    }
+
+    pub fn focus_next(&mut self) {
+        self.focus = (self.focus + 1) % self.fields.len();
+    }
+
+    pub fn focus_prev(&mut self) {
+        self.focus = (self.focus + self.fields.len() - 1) % self.fields.len();
+    }
+
+    pub fn focused_field(&self) -> Field {
+        self.fields[self.focus].field
+    }
+
+    pub fn set_value(&mut self, field: Field, value: &str) {
+        if let Some(f) = self.fields.iter_mut().find(|f| f.field == field) {
+            f.value = value.to_string();
+            f.touched = true;
+        }
+    }
+
+    pub fn push(&mut self, c: char) {
+        let f = &mut self.fields[self.focus];
+        f.value.push(c);
+        f.touched = true;
+    }
+
+    pub fn pop(&mut self) {
+        let f = &mut self.fields[self.focus];
+        f.value.pop();
+        f.touched = true;
+    }
+
+    pub fn touch_all(&mut self) {
+        for f in &mut self.fields {
+            f.touched = true;
+        }
+    }
+
+    /// First validation message, formatted for the footer status.
+    pub fn first_error(&self) -> Option<String> {
+        self.fields
+            .iter()
+            .find_map(|f| f.error.as_ref().map(|e| format!("{}: {}", f.field.label(), e)))
+    }
+
+    /// First warning, formatted like `first_error`.
+    pub fn first_warning(&self) -> Option<String> {
+        self.fields
+            .iter()
+            .find_map(|f| f.warning.as_ref().map(|w| format!("{}: {}", f.field.label(), w)))
+    }
+
+    /// Check every field, recording a message on each invalid one.
+    /// Returns true when the form can be saved.
+    pub fn validate(&mut self, conns: &[Connection]) -> bool {
+        let editing = self.editing;
+        let name = self.resolved_name();
+        let user_set = !self.value(Field::User).is_empty();
+
+        // Resolve jump hosts against the list as it would be after saving
+        let base = editing.map(|i| conns[i].clone()).unwrap_or_default();
+        let candidate = self.apply(&base);
+        let mut after = conns.to_vec();
+        match editing {
+            Some(i) => after[i] = candidate.clone(),
+            None => after.push(candidate.clone()),
+        }
+        let jump_error = jump_chain(&candidate, &after).err().map(|e| e.to_string());
+        let port_set = !self.value(Field::Port).is_empty();
+
+        for f in &mut self.fields {
+            let v = f.value.trim();
+            f.error = match f.field {
+                Field::Name
+                    if !name.is_empty()
+                        && conns
+                            .iter()
+                            .enumerate()
+                            .any(|(i, c)| c.name == name && Some(i) != editing) =>
+                {
+                    Some("name already in use".into())
+                }
+                Field::User if v.contains(char::is_whitespace) || v.contains('@') => {
+                    Some("no spaces or '@'".into())
+                }
+                Field::Host if v.is_empty() => Some("required".into()),
+                Field::Host if v.contains(char::is_whitespace) => Some("no spaces".into()),
+                Field::Host => match parse_host_spec(v) {
+                    Err(e) => Some(e.to_string()),
+                    Ok(spec) if !spec.user.is_empty() && user_set => {
+                        Some("user is also set in the User field".into())
+                    }
+                    Ok(spec) if spec.port.is_some() && port_set => {
+                        Some("port is also set in the Port field".into())
+                    }
+                    Ok(_) => None,
+                },
+                Field::Port if !v.is_empty() && !matches!(v.parse::<u16>(), Ok(p) if p > 0) => {
+                    Some("must be 1-65535".into())
+                }
+                Field::Term if v.contains(char::is_whitespace) => Some("no spaces".into()),
+                Field::Jump => jump_error.clone(),
+                Field::Transport => match Transport::parse(v) {
+                    Err(e) => Some(e.to_string()),
+                    Ok(Transport::Custom(bin)) if bin.contains('/') && !expand_tilde(&bin).is_file() => {
+                        Some("file not found".into())
+                    }
+                    Ok(_) => None,
+                },
+                Field::Forwards => forward_items(v)
+                    .find_map(|item| Forward::parse(item).err().map(|e| format!("{}: {:#}", item, e))),
+                Field::Env => option_items(v)
+                    .find_map(|item| parse_env(item).err().map(|e| e.to_string())),
+                Field::Options => option_items(v)
+                    .find_map(|item| parse_option(item).err().map(|e| e.to_string())),
+                _ => None,
+            };
+            f.warning = match f.field {
+                // Not an error, so a connection whose key is gone can still
+                // be edited; connecting checks for it again
+                Field::Identity if !v.is_empty() && !expand_tilde(v).is_file() => {
+                    Some("file not found".into())
+                }
+                Field::Options => {
+                    let unknown: Vec<String> = option_items(v)
+                        .filter_map(|item| parse_option(item).ok())
+                        .filter(|(k, _)| canonical_name(k).is_none())
+                        .map(|(k, _)| k)
+                        .collect();
+                    (!unknown.is_empty())
+                        .then(|| format!("unknown to OpenSSH: {}", unknown.join(", ")))
+                }
+                _ => None,
+            };
+        }
+        self.fields.iter().all(|f| f.error.is_none())
+    }
+
+    /// Name to save under: the Name field, or the host when left empty.
+    fn resolved_name(&self) -> String {
+        let name = self.value(Field::Name);
+        if !name.is_empty() {
+            return name.to_string();
+        }
+        parse_host_spec(self.value(Field::Host))
+            .map(|spec| spec.host)
+            .unwrap_or_default()
+    }
+
+    /// Build the connection described by the form on top of `base`, so
+    /// settings the form does not show are carried over unchanged. A
+    /// `user@host:port` typed into the Host field fills User and Port.
+    pub fn apply(&self, base: &Connection) -> Connection {
+        let opt = |v: &str| (!v.is_empty()).then(|| v.to_string());
+        let spec = parse_host_spec(self.value(Field::Host)).unwrap_or_else(|_| HostSpec {
+            user: String::new(),
+            host: self.value(Field::Host).to_string(),
+            port: None,
+        });
+        let group = self
+            .value(Field::Group)
+            .split('/')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .collect::<Vec<_>>()
+            .join("/");
+        let user = match self.value(Field::User) {
+            "" => spec.user,
+            u => u.to_string(),
+        };
+        let mut conn = base.clone();
+        conn.name = self.resolved_name();
+        conn.group = opt(&group);
+        conn.user = user;
+        conn.host = spec.host;
+        conn.port = self.value(Field::Port).parse().ok().or(spec.port);
+        conn.env = option_items(self.value(Field::Env))
+            .filter_map(|item| parse_env(item).ok())
+            .collect();
+        conn.set_term(opt(self.value(Field::Term)));
+        conn.identity_file = opt(self.value(Field::Identity));
+        conn.jump_hosts = split_list(self.value(Field::Jump));
+        conn.transport = Transport::parse(self.value(Field::Transport)).unwrap_or_default();
+        conn.forwards = forward_items(self.value(Field::Forwards))
+            .filter_map(|item| Forward::parse(item).ok())
+            .collect();
+        conn.remote_command = opt(self.value(Field::Command));
+        conn.options = option_items(self.value(Field::Options))
+            .filter_map(|item| parse_option(item).ok())
+            .collect();
+        conn.tags.clear();
+        conn.edit_tags(
+            split_list(self.value(Field::Tags))
+                .iter()
+                .map(|t| t.trim_start_matches(['+', '-'])),
+        );
+        conn
+    }
+}
+
+/// Items of a comma and/or space separated list, blanks dropped.
+fn split_list(s: &str) -> Vec<String> {
+    s.split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|t| !t.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Options and variables are semicolon separated, since values like
+/// `Ciphers` take commas.
+fn option_items(s: &str) -> impl Iterator<Item = &str> {
+    s.split(';').map(str::trim).filter(|t| !t.is_empty())
+}
+
+/// Forward specs are comma separated, since each contains a space.
+fn forward_items(s: &str) -> impl Iterator<Item = &str> {
+    s.split(',').map(str::trim).filter(|t| !t.is_empty())
+}
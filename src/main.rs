@@ -1,12 +1,21 @@
 mod app;
+mod cli;
+mod clipboard;
+mod completions;
 mod config;
+mod form;
 mod models;
+mod pick;
+mod query;
 mod ssh;
+mod ssh_config;
+mod ssh_options;
+mod tunnels;
 mod ui;
 
 use std::{io, time::Duration};
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use crossterm::{
     event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, KeyModifiers},
     execute,
@@ -14,9 +23,13 @@ use crossterm::{
 };
 use ratatui::{backend::CrosstermBackend, Terminal};
 
-use app::{AppState, Mode};
-use config::{add_from_line, load_connections, save_connections};
-use ssh::run_ssh;
+use app::{AppState, Mode, Picker};
+use config::{load_snapshot, load_usage, merge_connections, rename_usage, sync_connections};
+use form::{ConnectionForm, Field};
+use models::{jump_dependents, rename_jump_host};
+use ssh::{command_preview, list_private_keys, run_ssh};
+use ssh_config::{default_ssh_config_path, load_ssh_config};
+use tunnels::Tunnel;
 use ui::draw_ui;
 
 fn handle_input(app: &mut AppState, event: Event) -> Result<bool> {
@@ -25,10 +38,11 @@ fn handle_input(app: &mut AppState, event: Event) -> Result<bool> {
             if app.pending_delete {
                 match code {
                     KeyCode::Char('y') | KeyCode::Char('Y') => {
-                        if let Some(i) = app.filtered_indices.get(app.selected).cloned() {
+                        if let Some(i) = app.selected_connection() {
                             app.connections.remove(i);
-                            let _ = save_connections(&app.connections);
-                            app.status = "Deleted.".into();
+                            if save(app) {
+                                app.status = "Deleted.".into();
+                            }
                             app.apply_filter();
                         }
                         app.pending_delete = false;
@@ -40,33 +54,222 @@ fn handle_input(app: &mut AppState, event: Event) -> Result<bool> {
                 return Ok(false);
             }
 
+            if let Some(line) = app.dry_run.take() {
+                if code == KeyCode::Char('y') {
+                    app.status = match clipboard::copy(&line) {
+                        Ok(()) => "Copied the ssh command.".into(),
+                        Err(e) => format!("Copy failed: {}", e),
+                    };
+                }
+                return Ok(false);
+            }
+
+            if app.show_details {
+                app.show_details = false;
+                return Ok(false);
+            }
+
+            if app.show_help {
+                // The renderer clamps the scroll to the help text
+                match code {
+                    KeyCode::Up | KeyCode::Char('k') => {
+                        app.help_scroll = app.help_scroll.saturating_sub(1);
+                    }
+                    KeyCode::Down | KeyCode::Char('j') => app.help_scroll += 1,
+                    KeyCode::PageUp => app.help_scroll = app.help_scroll.saturating_sub(10),
+                    KeyCode::PageDown => app.help_scroll += 10,
+                    _ => app.show_help = false,
+                }
+                return Ok(false);
+            }
+
+            if let Some(picker) = &mut app.picker {
+                match code {
+                    KeyCode::Esc => app.picker = None,
+                    KeyCode::Up | KeyCode::Char('k') if picker.selected > 0 => {
+                        picker.selected -= 1;
+                    }
+                    KeyCode::Down | KeyCode::Char('j')
+                        if picker.selected + 1 < picker.items.len() =>
+                    {
+                        picker.selected += 1;
+                    }
+                    KeyCode::Enter => {
+                        if let Some(item) = picker.items.get(picker.selected).cloned() {
+                            let field = app.form.focused_field();
+                            let value = match (field, app.form.value(field)) {
+                                // Jump hosts accumulate, one pick per hop
+                                (Field::Jump, current) if !current.is_empty() => {
+                                    format!("{}, {}", current, item)
+                                }
+                                _ => item,
+                            };
+                            app.form.set_value(field, &value);
+                            app.form.validate(&app.connections);
+                        }
+                        app.picker = None;
+                    }
+                    _ => {}
+                }
+                return Ok(false);
+            }
+
             match app.mode {
-                Mode::Add => match code {
+                Mode::Add | Mode::Edit => match code {
                     KeyCode::Esc => {
                         app.mode = Mode::Normal;
-                        app.add_buffer.clear();
+                        app.form = ConnectionForm::default();
                         app.status.clear();
                     }
+                    KeyCode::Tab | KeyCode::Down => app.form.focus_next(),
+                    KeyCode::BackTab | KeyCode::Up => app.form.focus_prev(),
+                    KeyCode::Char('k')
+                        if modifiers.contains(KeyModifiers::CONTROL)
+                            && app.form.focused_field() == Field::Jump =>
+                    {
+                        let editing = app.form.editing;
+                        let names: Vec<String> = app
+                            .connections
+                            .iter()
+                            .enumerate()
+                            .filter(|&(i, _)| Some(i) != editing)
+                            .map(|(_, c)| c.name.clone())
+                            .collect();
+                        if !names.is_empty() {
+                            app.picker = Some(Picker {
+                                title: " ↪ Add a jump host ".into(),
+                                items: names,
+                                selected: 0,
+                            });
+                        }
+                    }
+                    KeyCode::Char('k')
+                        if modifiers.contains(KeyModifiers::CONTROL)
+                            && app.form.focused_field() == Field::Identity =>
+                    {
+                        let keys = list_private_keys();
+                        if keys.is_empty() {
+                            app.status = "No private keys found in ~/.ssh".into();
+                        } else {
+                            let current = app.form.value(Field::Identity);
+                            app.picker = Some(Picker {
+                                title: " 🔑 Pick a key ".into(),
+                                selected: keys.iter().position(|k| k == current).unwrap_or(0),
+                                items: keys,
+                            });
+                        }
+                    }
                     KeyCode::Enter => {
-                        match add_from_line(&app.add_buffer) {
-                            Ok(conn) => {
-                                app.connections.push(conn);
-                                let _ = save_connections(&app.connections);
-                                app.add_buffer.clear();
-                                app.mode = Mode::Normal;
-                                app.status = "Saved.".into();
-                                app.apply_filter();
+                        app.form.touch_all();
+                        if app.form.validate(&app.connections) {
+                            let mut warning = app.form.first_warning();
+                            match app.form.editing {
+                                Some(i) => {
+                                    let conn = app.form.apply(&app.connections[i]);
+                                    let old = std::mem::replace(&mut app.connections[i], conn);
+                                    let new = app.connections[i].name.clone();
+                                    if new != old.name {
+                                        rename_jump_host(&mut app.connections, &old.name, &new);
+                                        if let Err(e) = rename_usage(&old.name, &new) {
+                                            warning = Some(format!("could not move usage history: {}", e));
+                                        }
+                                        app.usage = load_usage().unwrap_or_default();
+                                    }
+                                }
+                                None => {
+                                    let conn = app.form.apply(&Default::default());
+                                    app.connections.push(conn);
+                                }
                             }
-                            Err(e) => {
-                                app.status = format!("Error: {}", e);
+                            if save(app) {
+                                app.status = match warning {
+                                    Some(w) => format!("Saved, but {}", w),
+                                    None => "Saved.".into(),
+                                };
                             }
+                            app.form = ConnectionForm::default();
+                            app.mode = Mode::Normal;
+                            app.apply_filter();
+                        } else {
+                            app.status = app.form.first_error().unwrap_or_default();
                         }
                     }
                     KeyCode::Backspace => {
-                        app.add_buffer.pop();
+                        app.form.pop();
+                        app.form.validate(&app.connections);
                     }
                     KeyCode::Char(c) => {
-                        app.add_buffer.push(c);
+                        app.form.push(c);
+                        app.form.validate(&app.connections);
+                    }
+                    _ => {}
+                },
+                Mode::Command => match code {
+                    KeyCode::Esc => {
+                        app.mode = Mode::Normal;
+                        app.command.clear();
+                    }
+                    KeyCode::Enter => {
+                        app.mode = Mode::Normal;
+                        let command = std::mem::take(&mut app.command);
+                        if let Some(i) = app.selected_connection() {
+                            connect(app, i, Some(&command));
+                        }
+                    }
+                    KeyCode::Char('u') if modifiers.contains(KeyModifiers::CONTROL) => {
+                        app.command.clear();
+                    }
+                    KeyCode::Backspace => {
+                        app.command.pop();
+                    }
+                    KeyCode::Char(c) => app.command.push(c),
+                    _ => {}
+                },
+                Mode::Tunnels => match code {
+                    KeyCode::Esc | KeyCode::Char('T') => {
+                        app.mode = Mode::Normal;
+                    }
+                    KeyCode::Up | KeyCode::Char('k') if app.tunnel_selected > 0 => {
+                        app.tunnel_selected -= 1;
+                    }
+                    KeyCode::Down | KeyCode::Char('j')
+                        if app.tunnel_selected + 1 < app.tunnels.len() =>
+                    {
+                        app.tunnel_selected += 1;
+                    }
+                    KeyCode::Char('x') | KeyCode::Char('d') => {
+                        let i = app.tunnel_selected;
+                        if let Some(t) = app.tunnels.get_mut(i) {
+                            // Stop a live tunnel; remove one that is already down
+                            if t.is_running() {
+                                t.stop();
+                                app.status = format!("Stopped tunnel {}", t.name);
+                            } else {
+                                app.tunnels.remove(i);
+                                app.tunnel_selected = i.min(app.tunnels.len().saturating_sub(1));
+                                if app.tunnels.is_empty() {
+                                    app.mode = Mode::Normal;
+                                }
+                            }
+                        }
+                    }
+                    KeyCode::Char('r') => {
+                        if let Some(t) = app.tunnels.get_mut(app.tunnel_selected) {
+                            t.stop();
+                            let restarted = app
+                                .connections
+                                .iter()
+                                .find(|c| c.name == t.name)
+                                .context("connection no longer exists")
+                                .and_then(|c| Tunnel::start(c, &app.connections));
+                            match restarted {
+                                Ok(new) => {
+                                    app.status = format!("Restarted tunnel {}", new.name);
+                                    *t = new;
+                                }
+                                Err(e) => app.status = format!("Tunnel error: {}", e),
+                            }
+                        }
                     }
                     _ => {}
                 },
@@ -78,13 +281,13 @@ fn handle_input(app: &mut AppState, event: Event) -> Result<bool> {
                         app.search.pop();
                         app.apply_filter();
                     }
+                    // Allow Ctrl+u to clear
+                    KeyCode::Char('u') if modifiers.contains(KeyModifiers::CONTROL) => {
+                        app.search.clear();
+                        app.apply_filter();
+                    }
                     KeyCode::Char(c) => {
-                        // Allow Ctrl+u to clear
-                        if modifiers.contains(KeyModifiers::CONTROL) && c == 'u' {
-                            app.search.clear();
-                        } else {
-                            app.search.push(c);
-                        }
+                        app.search.push(c);
                         app.apply_filter();
                     }
                     KeyCode::Enter => {
@@ -95,7 +298,8 @@ fn handle_input(app: &mut AppState, event: Event) -> Result<bool> {
                 Mode::Normal => match code {
                     KeyCode::Char('q') => return Ok(true),
                     KeyCode::Char('h') => {
-                        app.show_help = !app.show_help;
+                        app.show_help = true;
+                        app.help_scroll = 0;
                     }
                     KeyCode::Char('/') => {
                         app.mode = Mode::Search;
@@ -103,32 +307,134 @@ fn handle_input(app: &mut AppState, event: Event) -> Result<bool> {
                     }
                     KeyCode::Char('a') => {
                         app.mode = Mode::Add;
-                        app.add_buffer.clear();
+                        app.form = ConnectionForm::new();
                         app.status.clear();
                     }
+                    KeyCode::Char('e') => {
+                        if let Some(i) = app.selected_connection() {
+                            app.form = ConnectionForm::from_connection(&app.connections[i], Some(i));
+                            app.mode = Mode::Edit;
+                            app.status.clear();
+                        }
+                    }
                     KeyCode::Char('d') => {
-                        app.pending_delete = true;
+                        let users = app
+                            .selected_connection()
+                            .map(|i| jump_dependents(&app.connections, &app.connections[i].name))
+                            .unwrap_or_default();
+                        if users.is_empty() {
+                            app.pending_delete = true;
+                        } else {
+                            app.status = format!("Cannot delete, it is the jump host of {}", users.join(", "));
+                        }
                     }
-                    KeyCode::Up | KeyCode::Char('k') => {
-                        if app.selected > 0 {
-                            app.selected -= 1;
+                    KeyCode::Char('v') if app.selected_connection().is_some() => {
+                        app.show_details = true;
+                    }
+                    KeyCode::Char('p') => {
+                        if let Some(i) = app.selected_connection() {
+                            match command_preview(&app.connections[i], &app.connections) {
+                                Ok(line) => app.dry_run = Some(line),
+                                Err(e) => app.status = format!("SSH error: {}", e),
+                            }
                         }
                     }
-                    KeyCode::Down | KeyCode::Char('j') => {
-                        if app.selected + 1 < app.filtered_indices.len() {
-                            app.selected += 1;
+                    KeyCode::Char('c') => {
+                        if let Some(i) = app.selected_connection() {
+                            app.command = app.connections[i].remote_command.clone().unwrap_or_default();
+                            app.mode = Mode::Command;
+                            app.status.clear();
                         }
                     }
-                    KeyCode::Enter => {
-                        if let Some(i) = app.filtered_indices.get(app.selected).cloned() {
-                            let conn = app.connections[i].clone();
-                            if let Err(e) = run_ssh(&conn) {
-                                app.status = format!("SSH error: {}", e);
+                    KeyCode::Char('i') => {
+                        let imported = default_ssh_config_path().and_then(|p| load_ssh_config(&p));
+                        match imported {
+                            Ok((incoming, warnings)) => {
+                                let report = merge_connections(&mut app.connections, incoming);
+                                app.status = format!("Imported ~/.ssh/config: {}", report.summary());
+                                if !warnings.is_empty() {
+                                    app.status.push_str(&format!(
+                                        "; skipped {} unsupported forward(s), see `jumpseat import`",
+                                        warnings.len()
+                                    ));
+                                }
+                                if !report.added.is_empty() {
+                                    save(app);
+                                    app.apply_filter();
+                                }
+                            }
+                            Err(e) => {
+                                app.status = format!("Import error: {}", e);
+                            }
+                        }
+                    }
+                    KeyCode::Up | KeyCode::Char('k') if app.selected > 0 => {
+                        app.selected -= 1;
+                    }
+                    KeyCode::Down | KeyCode::Char('j')
+                        if app.selected + 1 < app.rows.len() =>
+                    {
+                        app.selected += 1;
+                    }
+                    KeyCode::Char(' ') | KeyCode::Enter if app.selected_group().is_some() => {
+                        if let Some(path) = app.selected_group().map(str::to_string) {
+                            app.toggle_group(&path);
+                        }
+                    }
+                    KeyCode::Left => {
+                        // On a host, fold the group it lives in
+                        let path = match app.selected_connection() {
+                            Some(i) if app.search.trim().is_empty() => {
+                                Some(app.connections[i].group_name()).filter(|p| !p.is_empty())
+                            }
+                            Some(_) => None,
+                            None => app.selected_group().map(str::to_string),
+                        };
+                        if let Some(path) = path {
+                            app.set_collapsed(&path, true);
+                        }
+                    }
+                    KeyCode::Right => {
+                        if let Some(path) = app.selected_group().map(str::to_string) {
+                            app.set_collapsed(&path, false);
+                        }
+                    }
+                    KeyCode::Char('t') => {
+                        if let Some(i) = app.selected_connection() {
+                            let name = &app.connections[i].name;
+                            if app.tunnels.iter().any(|t| &t.name == name && t.is_running()) {
+                                app.status = format!("Tunnel {} is already running", name);
                             } else {
-                                app.status = format!("Returned from {}", conn.name);
+                                match Tunnel::start(&app.connections[i], &app.connections) {
+                                    Ok(t) => {
+                                        app.status = format!("Started tunnel {}", t.name);
+                                        app.tunnels.retain(|old| old.name != t.name);
+                                        app.tunnels.push(t);
+                                    }
+                                    Err(e) => app.status = format!("Tunnel error: {}", e),
+                                }
                             }
                         }
                     }
+                    KeyCode::Char('T') if !app.tunnels.is_empty() => {
+                        app.mode = Mode::Tunnels;
+                        app.tunnel_selected = app.tunnel_selected.min(app.tunnels.len() - 1);
+                    }
+                    KeyCode::Char('s') => {
+                        app.sort_by_name = !app.sort_by_name;
+                        app.apply_filter();
+                        app.status = if app.sort_by_name {
+                            "Sorted by name.".into()
+                        } else {
+                            "Sorted by frecency.".into()
+                        };
+                    }
+                    KeyCode::Enter => {
+                        if let Some(i) = app.selected_connection() {
+                            let command = app.connections[i].remote_command.clone();
+                            connect(app, i, command.as_deref());
+                        }
+                    }
                     _ => {}
                 },
             }
@@ -139,10 +445,62 @@ fn handle_input(app: &mut AppState, event: Event) -> Result<bool> {
     Ok(false)
 }
 
+/// Write the connections to disk, merging in changes other instances made
+/// meanwhile. On failure the edit stays in memory, the error goes to the
+/// status line, and the next successful save persists it. Returns true for
+/// a plain save, so the caller can report its own success; otherwise the
+/// status already says what happened. Callers should rebuild the list
+/// afterwards, since a merge can change `connections`.
+fn save(app: &mut AppState) -> bool {
+    match sync_connections(&mut app.saved, &mut app.connections) {
+        Ok(report) if !report.conflicts.is_empty() => {
+            app.status = format!(
+                "Saved, merged with changes on disk; kept this version of {}",
+                report.conflicts.join(", ")
+            );
+            false
+        }
+        Ok(report) => {
+            if report.merged {
+                app.status = "Saved, merged with changes made on disk.".into();
+                return false;
+            }
+            true
+        }
+        Err(e) => {
+            app.status = format!("Not saved: {:#}", e);
+            false
+        }
+    }
+}
+
+/// Run ssh for connection `i` and refresh the list (and frecency) afterwards.
+fn connect(app: &mut AppState, i: usize, command: Option<&str>) {
+    let conn = app.connections[i].clone();
+    if let Err(e) = run_ssh(&conn, &app.connections, command) {
+        app.status = format!("SSH error: {}", e);
+    } else {
+        app.status = format!("Returned from {}", conn.name);
+    }
+    app.usage = load_usage().unwrap_or_default();
+    app.apply_filter();
+    app.select_connection(i);
+}
+
 fn main() -> Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        return cli::run(&args);
+    }
+
     // Load
-    let mut app = AppState::default();
-    app.connections = load_connections()?;
+    let saved = load_snapshot()?;
+    let mut app = AppState {
+        connections: saved.connections.clone(),
+        saved,
+        usage: load_usage().unwrap_or_default(),
+        ..Default::default()
+    };
     app.apply_filter();
 
     // TUI init
@@ -155,18 +513,35 @@ fn main() -> Result<()> {
 
     // Event loop
     loop {
+        for t in &mut app.tunnels {
+            t.poll();
+        }
+
+        // Reload edits made elsewhere, but not under an open form or delete
+        // prompt, which refer to connections by index
+        if !matches!(app.mode, Mode::Add | Mode::Edit) && !app.pending_delete {
+            match app.reload() {
+                Ok(true) => app.status = "Reloaded connections.json".into(),
+                Ok(false) => {}
+                Err(e) => app.status = format!("Cannot reload: {:#}", e),
+            }
+        }
+
         // UI
         terminal.draw(|f| draw_ui(f, &mut app))?;
 
         // Input
-        if crossterm::event::poll(Duration::from_millis(200))? {
-            if handle_input(&mut app, event::read()?)? {
-                break;
-            }
+        if crossterm::event::poll(Duration::from_millis(200))?
+            && handle_input(&mut app, event::read()?)?
+        {
+            break;
         }
     }
 
     // Cleanup
+    for t in &mut app.tunnels {
+        t.stop();
+    }
     disable_raw_mode()?;
     execute!(
         io::stdout(),
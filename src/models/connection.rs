@@ -1,18 +1,285 @@
+use anyhow::{bail, Result};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+use super::{Forward, Transport};
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
 pub struct Connection {
     pub name: String,
+    /// Empty means "let ssh pick" (local user or ~/.ssh/config)
     pub user: String,
     pub host: String,
     pub port: Option<u16>,
-    /// If None, defaults to "xterm-256color" on connect
-    pub term: Option<String>,
+    /// Variables set for ssh and sent on with `SendEnv`. `TERM` lives here
+    /// too and defaults to xterm-256color on connect.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>,
+    /// `term` as written by versions before `env`; see `migrate`
+    #[serde(default, rename = "term", skip_serializing)]
+    legacy_term: Option<String>,
+    /// Slash-separated folder path, e.g. `prod/eu/db`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Passed to ssh as `IdentityFile`, with `IdentitiesOnly`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub identity_file: Option<String>,
+    /// Names of other connections to hop through, outermost first. An entry
+    /// that names no connection but contains `@` is used as a literal
+    /// `user@host[:port]`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub jump_hosts: Vec<String>,
+    /// Port forwards set up on every connect
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub forwards: Vec<Forward>,
+    /// Run on the server instead of a login shell, e.g. `tmux new -A -s main`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub remote_command: Option<String>,
+    /// ssh, a custom ssh binary, mosh or et
+    #[serde(default, skip_serializing_if = "Transport::is_ssh")]
+    pub transport: Transport,
+    /// Extra ssh options, passed as `-o Key=Value`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub options: BTreeMap<String, String>,
 }
 
 impl Connection {
-    pub fn label(&self) -> String {
+    /// Move settings read from older file formats to where they live now.
+    pub fn migrate(&mut self) {
+        if let Some(term) = self.legacy_term.take() {
+            self.env.entry("TERM".to_string()).or_insert(term);
+        }
+    }
+
+    pub fn term(&self) -> Option<&str> {
+        self.env.get("TERM").map(String::as_str)
+    }
+
+    pub fn set_term(&mut self, term: Option<String>) {
+        match term {
+            Some(t) => self.env.insert("TERM".to_string(), t),
+            None => self.env.remove("TERM"),
+        };
+    }
+
+    /// `[user@]host[:port]` in the form `ssh -J` expects.
+    pub fn jump_spec(&self) -> String {
+        let host = if self.host.contains(':') {
+            format!("[{}]", self.host)
+        } else {
+            self.host.clone()
+        };
         let port = self.port.map(|p| format!(":{}", p)).unwrap_or_default();
-        format!("{}  {}@{}{}", self.name, self.user, self.host, port)
+        if self.user.is_empty() {
+            format!("{}{}", host, port)
+        } else {
+            format!("{}@{}{}", self.user, host, port)
+        }
+    }
+
+    /// `host[:port]`, bracketing IPv6 hosts when a port follows.
+    pub fn host_port(&self) -> String {
+        match self.port {
+            Some(p) if self.host.contains(':') => format!("[{}]:{}", self.host, p),
+            Some(p) => format!("{}:{}", self.host, p),
+            None => self.host.clone(),
+        }
+    }
+
+    /// Non-empty segments of `group`, outermost first.
+    pub fn group_path(&self) -> impl Iterator<Item = &str> {
+        self.group
+            .as_deref()
+            .unwrap_or_default()
+            .split('/')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+    }
+
+    /// `group` with stray slashes and blanks removed; empty when ungrouped.
+    pub fn group_name(&self) -> String {
+        self.group_path().collect::<Vec<_>>().join("/")
+    }
+
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t.eq_ignore_ascii_case(tag))
+    }
+
+    /// Add `+tag` / remove `-tag` (a bare `tag` adds), keeping tags unique.
+    pub fn edit_tags<'a>(&mut self, edits: impl IntoIterator<Item = &'a str>) {
+        for edit in edits {
+            if let Some(t) = edit.strip_prefix('-') {
+                self.tags.retain(|x| !x.eq_ignore_ascii_case(t));
+            } else {
+                let t = edit.strip_prefix('+').unwrap_or(edit).trim();
+                if !t.is_empty() && !self.has_tag(t) {
+                    self.tags.push(t.to_string());
+                }
+            }
+        }
+    }
+
+    /// `user@host`, or just `host` when no user is set.
+    pub fn destination(&self) -> String {
+        if self.user.is_empty() {
+            self.host.clone()
+        } else {
+            format!("{}@{}", self.user, self.host)
+        }
+    }
+}
+
+/// One step on the way to a connection.
+pub enum Hop<'a> {
+    Connection(&'a Connection),
+    /// A `user@host` jump entry that is not a saved connection
+    Literal(&'a str),
+}
+
+impl Hop<'_> {
+    pub fn name(&self) -> &str {
+        match self {
+            Hop::Connection(c) => &c.name,
+            Hop::Literal(s) => s,
+        }
     }
-}
\ No newline at end of file
+
+    pub fn jump_spec(&self) -> String {
+        match self {
+            Hop::Connection(c) => c.jump_spec(),
+            Hop::Literal(s) => s.to_string(),
+        }
+    }
+}
+
+/// Expand `conn.jump_hosts` into the full ordered list of hops, following
+/// jump hosts of jump hosts. Fails on unknown names and on cycles.
+pub fn jump_chain<'a>(conn: &'a Connection, all: &'a [Connection]) -> Result<Vec<Hop<'a>>> {
+    fn visit<'a>(
+        conn: &'a Connection,
+        all: &'a [Connection],
+        path: &mut Vec<&'a str>,
+        hops: &mut Vec<Hop<'a>>,
+    ) -> Result<()> {
+        for name in &conn.jump_hosts {
+            let Some(jump) = all.iter().find(|c| &c.name == name) else {
+                if name.contains('@') {
+                    hops.push(Hop::Literal(name));
+                    continue;
+                }
+                bail!("unknown jump host '{}' (used by {})", name, conn.name);
+            };
+            if path.contains(&jump.name.as_str()) {
+                bail!("jump host loop: {} → {}", path.join(" → "), jump.name);
+            }
+            path.push(&jump.name);
+            visit(jump, all, path, hops)?;
+            path.pop();
+            // Two jump hosts sharing a bastion only need to pass it once
+            if !hops.iter().any(|h| h.name() == jump.name) {
+                hops.push(Hop::Connection(jump));
+            }
+        }
+        Ok(())
+    }
+
+    let mut hops = Vec::new();
+    visit(conn, all, &mut vec![conn.name.as_str()], &mut hops)?;
+    Ok(hops)
+}
+
+/// Names of the connections that list `name` as a jump host.
+pub fn jump_dependents<'a>(conns: &'a [Connection], name: &str) -> Vec<&'a str> {
+    conns
+        .iter()
+        .filter(|c| c.jump_hosts.iter().any(|j| j == name))
+        .map(|c| c.name.as_str())
+        .collect()
+}
+
+/// After a rename, point jump host entries that named `old` at `new`.
+/// Returns how many connections were changed.
+pub fn rename_jump_host(conns: &mut [Connection], old: &str, new: &str) -> usize {
+    let mut changed = 0;
+    for conn in conns {
+        let mut hit = false;
+        for name in conn.jump_hosts.iter_mut().filter(|n| *n == old) {
+            *name = new.to_string();
+            hit = true;
+        }
+        changed += hit as usize;
+    }
+    changed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Connection names, each with its jump hosts.
+    type Defs<'a> = &'a [(&'a str, &'a [&'a str])];
+
+    /// Connections at `name.example`, each with the given jump hosts.
+    fn conns(defs: Defs) -> Vec<Connection> {
+        defs.iter()
+            .map(|(name, jumps)| Connection {
+                name: name.to_string(),
+                host: format!("{}.example", name),
+                jump_hosts: jumps.iter().map(|j| j.to_string()).collect(),
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    /// The hops of the first connection as `-J` specs, or the error.
+    fn chain(defs: Defs) -> Result<Vec<String>, String> {
+        let all = conns(defs);
+        jump_chain(&all[0], &all)
+            .map(|hops| hops.iter().map(Hop::jump_spec).collect())
+            .map_err(|e| e.to_string())
+    }
+
+    #[test]
+    fn jump_chains() {
+        let cases: [(Defs, Vec<&str>); 4] = [
+            (&[("app", &[])], vec![]),
+            // Jump hosts of jump hosts come first
+            (
+                &[("app", &["bastion"]), ("bastion", &["gateway"]), ("gateway", &[])],
+                vec!["gateway.example", "bastion.example"],
+            ),
+            // Two jump hosts behind one bastion pass it once
+            (
+                &[("app", &["a", "b"]), ("a", &["bastion"]), ("b", &["bastion"]), ("bastion", &[])],
+                vec!["bastion.example", "a.example", "b.example"],
+            ),
+            // A user@host entry that names no connection is a hop as is
+            (&[("app", &["ops@10.0.0.1:2222"])], vec!["ops@10.0.0.1:2222"]),
+        ];
+        for (defs, want) in cases {
+            let want = want.iter().map(|s| s.to_string()).collect();
+            assert_eq!(chain(defs), Ok(want), "{:?}", defs);
+        }
+    }
+
+    #[test]
+    fn jump_chain_errors() {
+        let cases: [(Defs, &str); 4] = [
+            (&[("app", &["nope"])], "unknown jump host 'nope' (used by app)"),
+            (
+                &[("app", &["bastion"]), ("bastion", &["gone"])],
+                "unknown jump host 'gone' (used by bastion)",
+            ),
+            (&[("app", &["app"])], "jump host loop: app → app"),
+            (
+                &[("app", &["a"]), ("a", &["b"]), ("b", &["a"])],
+                "jump host loop: app → a → b → a",
+            ),
+        ];
+        for (defs, want) in cases {
+            assert_eq!(chain(defs), Err(want.to_string()), "{:?}", defs);
+        }
+    }
+}
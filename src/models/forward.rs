@@ -0,0 +1,185 @@
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ForwardKind {
+    /// `-L`: a local port reaches `target` through the server
+    Local,
+    /// `-R`: a port on the server reaches `target` through this machine
+    Remote,
+    /// `-D`: a local SOCKS proxy
+    Dynamic,
+}
+
+impl ForwardKind {
+    pub fn flag(self) -> &'static str {
+        match self {
+            ForwardKind::Local => "-L",
+            ForwardKind::Remote => "-R",
+            ForwardKind::Dynamic => "-D",
+        }
+    }
+
+    fn letter(self) -> char {
+        match self {
+            ForwardKind::Local => 'L',
+            ForwardKind::Remote => 'R',
+            ForwardKind::Dynamic => 'D',
+        }
+    }
+}
+
+/// One port forward, written as `L [bind:]port:host:hostport`,
+/// `R [bind:]port:host:hostport` or `D [bind:]port`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Forward {
+    pub kind: ForwardKind,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bind_address: Option<String>,
+    pub port: u16,
+    /// `(host, port)` the forward leads to; None for dynamic forwards
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target: Option<(String, u16)>,
+}
+
+impl Forward {
+    pub fn parse(s: &str) -> Result<Forward> {
+        let s = s.trim();
+        let (kind, spec) = match s.split_once(char::is_whitespace) {
+            Some((k, spec)) => (k, spec.trim()),
+            None => bail!("expected 'L|R|D <spec>', got '{}'", s),
+        };
+        let kind = match kind.to_ascii_uppercase().trim_start_matches('-') {
+            "L" => ForwardKind::Local,
+            "R" => ForwardKind::Remote,
+            "D" => ForwardKind::Dynamic,
+            other => bail!("unknown forward type '{}' (use L, R or D)", other),
+        };
+        Self::parse_spec(kind, spec)
+    }
+
+    /// Parse the part after the flag, as ssh takes it on the command line.
+    pub fn parse_spec(kind: ForwardKind, spec: &str) -> Result<Forward> {
+        let parts = split_colons(spec)?;
+        let (bind, port, target) = match (kind, parts.as_slice()) {
+            (ForwardKind::Dynamic, [port]) => (None, port, None),
+            (ForwardKind::Dynamic, [bind, port]) => (Some(bind), port, None),
+            (ForwardKind::Dynamic, _) => bail!("dynamic forward is [bind:]port"),
+            (_, [port, host, hport]) => (None, port, Some((host, hport))),
+            (_, [bind, port, host, hport]) => (Some(bind), port, Some((host, hport))),
+            _ => bail!("forward is [bind:]port:host:hostport"),
+        };
+
+        let bind_address = match bind {
+            Some(b) => {
+                check_host(b).with_context(|| format!("bind address '{}'", b))?;
+                Some(b.to_string())
+            }
+            None => None,
+        };
+        let target = match target {
+            Some((host, hport)) => {
+                check_host(host).with_context(|| format!("target host '{}'", host))?;
+                Some((host.to_string(), parse_port(hport)?))
+            }
+            None => None,
+        };
+        Ok(Forward {
+            kind,
+            bind_address,
+            port: parse_port(port)?,
+            target,
+        })
+    }
+
+    /// Argument for the ssh flag, e.g. `127.0.0.1:8080:db:5432`.
+    pub fn spec(&self) -> String {
+        match self.target_spec() {
+            Some(target) => format!("{}:{}", self.listen_spec(), target),
+            None => self.listen_spec(),
+        }
+    }
+
+    /// `[bind:]port`
+    pub fn listen_spec(&self) -> String {
+        match &self.bind_address {
+            Some(b) => format!("{}:{}", bracket(b), self.port),
+            None => self.port.to_string(),
+        }
+    }
+
+    /// `host:hostport`, None for dynamic forwards
+    pub fn target_spec(&self) -> Option<String> {
+        self.target
+            .as_ref()
+            .map(|(host, port)| format!("{}:{}", bracket(host), port))
+    }
+}
+
+impl fmt::Display for Forward {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.kind.letter(), self.spec())
+    }
+}
+
+/// Split on `:` outside of `[...]`, dropping the brackets.
+fn split_colons(s: &str) -> Result<Vec<&str>> {
+    let mut parts = Vec::new();
+    let mut rest = s;
+    loop {
+        if let Some(inner) = rest.strip_prefix('[') {
+            let (host, after) = inner.split_once(']').context("missing ']'")?;
+            parts.push(host);
+            match after.strip_prefix(':') {
+                Some(r) => rest = r,
+                None if after.is_empty() => break,
+                None => bail!("unexpected '{}' after ']'", after),
+            }
+        } else {
+            match rest.split_once(':') {
+                Some((p, r)) => {
+                    parts.push(p);
+                    rest = r;
+                }
+                None => {
+                    parts.push(rest);
+                    break;
+                }
+            }
+        }
+    }
+    Ok(parts)
+}
+
+fn parse_port(p: &str) -> Result<u16> {
+    match p.parse::<u16>() {
+        Ok(port) if port > 0 => Ok(port),
+        _ => bail!("invalid port '{}' (expected 1-65535)", p),
+    }
+}
+
+fn check_host(h: &str) -> Result<()> {
+    if h.is_empty() {
+        bail!("is empty");
+    }
+    if h == "*" || h.parse::<std::net::IpAddr>().is_ok() {
+        return Ok(());
+    }
+    let hostname_ok = h
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '.' || c == '_');
+    if !hostname_ok {
+        bail!("is not an IP address or host name");
+    }
+    Ok(())
+}
+
+fn bracket(host: &str) -> String {
+    if host.contains(':') {
+        format!("[{}]", host)
+    } else {
+        host.to_string()
+    }
+}
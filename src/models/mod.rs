@@ -1,3 +1,9 @@
 pub mod connection;
+pub mod forward;
+pub mod transport;
+pub mod usage;
 
-pub use connection::Connection;
\ No newline at end of file
+pub use connection::{jump_chain, jump_dependents, rename_jump_host, Connection, Hop};
+pub use forward::{Forward, ForwardKind};
+pub use transport::Transport;
+pub use usage::Usage;
@@ -0,0 +1,69 @@
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::Path;
+
+/// Program used to open the interactive session. Tunnels always use ssh
+/// (or the custom binary, which is assumed to take ssh's flags).
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Transport {
+    #[default]
+    Ssh,
+    /// An ssh-compatible binary, by path or looked up in PATH
+    Custom(String),
+    /// mosh, bootstrapped over ssh with the connection's ssh settings
+    Mosh,
+    /// Eternal Terminal (`et`), likewise bootstrapped over ssh
+    Et,
+}
+
+impl Transport {
+    /// `ssh`, `mosh`, `et`, or anything else as a custom binary. Empty means ssh.
+    pub fn parse(s: &str) -> Result<Transport> {
+        let s = s.trim();
+        Ok(match s {
+            "" | "ssh" => Transport::Ssh,
+            "mosh" => Transport::Mosh,
+            "et" => Transport::Et,
+            bin if bin.contains(char::is_whitespace) => {
+                bail!("binary path cannot contain spaces")
+            }
+            bin => Transport::Custom(bin.to_string()),
+        })
+    }
+
+    pub fn is_ssh(&self) -> bool {
+        *self == Transport::Ssh
+    }
+
+    /// Program that takes ssh's own flags: the custom binary, or ssh.
+    pub fn ssh_program(&self) -> &str {
+        match self {
+            Transport::Custom(bin) => bin,
+            _ => "ssh",
+        }
+    }
+
+    /// Short name for the list, e.g. `mosh` or the custom binary's file name.
+    pub fn label(&self) -> String {
+        match self {
+            Transport::Custom(bin) => Path::new(bin)
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_else(|| bin.clone()),
+            other => other.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Transport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Transport::Ssh => write!(f, "ssh"),
+            Transport::Custom(bin) => write!(f, "{}", bin),
+            Transport::Mosh => write!(f, "mosh"),
+            Transport::Et => write!(f, "et"),
+        }
+    }
+}
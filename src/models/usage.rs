@@ -0,0 +1,39 @@
+use serde::{Deserialize, Serialize};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// How often and how recently a connection has been used.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct Usage {
+    /// Unix timestamp (seconds) of the last connect
+    pub last_connected: u64,
+    pub connect_count: u32,
+}
+
+impl Usage {
+    pub fn record(&mut self) {
+        self.last_connected = now();
+        self.connect_count += 1;
+    }
+
+    /// Use count weighted by how recently the connection was last used, so a
+    /// host used daily this week beats one used a lot last year.
+    pub fn frecency(&self, now: u64) -> u64 {
+        const DAY: u64 = 24 * 60 * 60;
+        let age = now.saturating_sub(self.last_connected);
+        let weight = match age {
+            a if a < 4 * DAY => 100,
+            a if a < 14 * DAY => 70,
+            a if a < 31 * DAY => 50,
+            a if a < 90 * DAY => 30,
+            _ => 10,
+        };
+        self.connect_count as u64 * weight
+    }
+}
+
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
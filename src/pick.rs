@@ -0,0 +1,210 @@
+use anyhow::{bail, Context, Result};
+use crossterm::{
+    event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    terminal::{self, disable_raw_mode, enable_raw_mode},
+};
+use ratatui::{backend::CrosstermBackend, layout::Rect, Terminal, TerminalOptions, Viewport};
+use std::{
+    fs::OpenOptions,
+    io::{self, Read, Write},
+    process,
+    sync::mpsc,
+    thread,
+    time::Duration,
+};
+
+use crate::app::{AppState, Mode};
+use crate::config::{load_connections, load_usage};
+use crate::ssh::{shell_quote, ssh_args};
+use crate::ui::draw_pick_ui;
+
+/// Rows the inline picker takes at most, borders included.
+const MAX_HEIGHT: u16 = 16;
+
+/// Exit status on Esc / Ctrl-C, as fzf uses.
+const CANCELLED: i32 = 130;
+
+/// What `pick` prints for the chosen connection.
+enum Format {
+    Name,
+    /// `user@host`, or just `host` when no user is set
+    Dest,
+    /// ssh's arguments, shell-quoted, for `eval ssh $(jumpseat pick --format argv)`
+    Argv,
+}
+
+/// `jumpseat pick [--format name|dest|argv] [query]`: choose a connection in a
+/// search-and-list UI drawn inline on stderr, then print it to stdout.
+pub fn run(args: &[String]) -> Result<()> {
+    let mut format = Format::Name;
+    let mut query = Vec::new();
+    let mut it = args.iter();
+    while let Some(arg) = it.next() {
+        match arg.as_str() {
+            "-f" | "--format" => {
+                format = match it.next().map(String::as_str) {
+                    Some("name") => Format::Name,
+                    Some("dest") => Format::Dest,
+                    Some("argv") => Format::Argv,
+                    _ => bail!("--format takes name, dest or argv"),
+                };
+            }
+            word => query.push(word),
+        }
+    }
+
+    let mut app = AppState {
+        connections: load_connections()?,
+        usage: load_usage().unwrap_or_default(),
+        search: query.join(" "),
+        mode: Mode::Search,
+        ..Default::default()
+    };
+    app.apply_filter();
+
+    let Some(i) = choose(&mut app)? else {
+        process::exit(CANCELLED);
+    };
+    let conn = &app.connections[i];
+    match format {
+        Format::Name => println!("{}", conn.name),
+        Format::Dest => println!("{}", conn.destination()),
+        Format::Argv => {
+            let args: Vec<String> = ssh_args(conn, &app.connections)?
+                .iter()
+                .map(|a| shell_quote(a))
+                .collect();
+            println!("{}", args.join(" "));
+        }
+    }
+    Ok(())
+}
+
+/// Run the picker until a connection is chosen (Some) or it is cancelled.
+/// stdout is left alone so the caller can capture the result.
+fn choose(app: &mut AppState) -> Result<Option<usize>> {
+    enable_raw_mode()?;
+    let result = reserve_area(app.connections.len() as u16 + 5).and_then(|area| {
+        let mut terminal = Terminal::with_options(
+            CrosstermBackend::new(io::stderr()),
+            TerminalOptions {
+                viewport: Viewport::Fixed(area),
+            },
+        )?;
+        let choice = pick_loop(&mut terminal, app);
+        // Leave the shell prompt where the picker was
+        terminal.clear()?;
+        terminal.set_cursor_position(area.as_position())?;
+        terminal.show_cursor()?;
+        choice
+    });
+    disable_raw_mode()?;
+    result
+}
+
+fn pick_loop(
+    terminal: &mut Terminal<CrosstermBackend<io::Stderr>>,
+    app: &mut AppState,
+) -> Result<Option<usize>> {
+    loop {
+        terminal.draw(|f| draw_pick_ui(f, app))?;
+        if let Event::Key(key) = event::read()? {
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            if let Some(done) = handle_key(app, key) {
+                return Ok(done);
+            }
+        }
+    }
+}
+
+/// Make room for `height` rows below the cursor, scrolling the terminal up
+/// when too close to the bottom, and return that area.
+fn reserve_area(height: u16) -> Result<Rect> {
+    let (cols, rows) = terminal::size()?;
+    let height = height.min(MAX_HEIGHT).min(rows);
+    let row = cursor_row()?;
+    let mut err = io::stderr();
+    // Newlines scroll once they reach the last line
+    write!(err, "{}", "\n".repeat(height.saturating_sub(1) as usize))?;
+    err.flush()?;
+    Ok(Rect::new(0, row.min(rows - height), cols, height))
+}
+
+/// Ask the terminal which row the cursor is on (0-based). crossterm's own
+/// query is written to stdout, which `$(jumpseat pick)` would capture, so
+/// this talks to the tty directly. Raw mode must be on.
+fn cursor_row() -> Result<u16> {
+    let mut tty = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open("/dev/tty")
+        .context("pick needs a terminal")?;
+    tty.write_all(b"\x1b[6n")?;
+    tty.flush()?;
+
+    // The reply is ESC [ row ; col R. Read it on a thread so a terminal
+    // that never answers cannot hang us.
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let mut reply = Vec::new();
+        let mut byte = [0u8; 1];
+        while let Ok(1) = tty.read(&mut byte) {
+            reply.push(byte[0]);
+            if byte[0] == b'R' {
+                break;
+            }
+        }
+        let _ = tx.send(reply);
+    });
+    let reply = rx
+        .recv_timeout(Duration::from_secs(2))
+        .context("the terminal did not report the cursor position")?;
+    let reply = String::from_utf8_lossy(&reply);
+    reply
+        .rsplit('[')
+        .next()
+        .and_then(|r| r.split(';').next())
+        .and_then(|row| row.parse::<u16>().ok())
+        .map(|row| row.saturating_sub(1))
+        .context("unexpected reply to the cursor position query")
+}
+
+/// Returns Some once the picker is done: Some(Some(i)) for a choice,
+/// Some(None) for cancel.
+fn handle_key(app: &mut AppState, KeyEvent { code, modifiers, .. }: KeyEvent) -> Option<Option<usize>> {
+    let ctrl = modifiers.contains(KeyModifiers::CONTROL);
+    match code {
+        KeyCode::Esc => return Some(None),
+        KeyCode::Char('c') if ctrl => return Some(None),
+        KeyCode::Enter => {
+            if let Some(i) = app.selected_connection() {
+                return Some(Some(i));
+            }
+            if let Some(path) = app.selected_group().map(str::to_string) {
+                app.toggle_group(&path);
+            }
+        }
+        KeyCode::Up if app.selected > 0 => app.selected -= 1,
+        KeyCode::Char('p' | 'k') if ctrl && app.selected > 0 => app.selected -= 1,
+        KeyCode::Down if app.selected + 1 < app.rows.len() => app.selected += 1,
+        KeyCode::Char('n' | 'j') if ctrl && app.selected + 1 < app.rows.len() => {
+            app.selected += 1;
+        }
+        KeyCode::Char('u') if ctrl => {
+            app.search.clear();
+            app.apply_filter();
+        }
+        KeyCode::Backspace => {
+            app.search.pop();
+            app.apply_filter();
+        }
+        KeyCode::Char(c) if !ctrl => {
+            app.search.push(c);
+            app.apply_filter();
+        }
+        _ => {}
+    }
+    None
+}
@@ -0,0 +1,345 @@
+use crate::models::Connection;
+
+/// Connection attribute a search term can be scoped to with `field:value`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum QueryField {
+    Name,
+    User,
+    Host,
+    Port,
+    Term,
+    Tag,
+    Group,
+}
+
+impl QueryField {
+    fn from_prefix(prefix: &str) -> Option<Self> {
+        match prefix.to_ascii_lowercase().as_str() {
+            "name" => Some(QueryField::Name),
+            "user" => Some(QueryField::User),
+            "host" => Some(QueryField::Host),
+            "port" => Some(QueryField::Port),
+            "term" => Some(QueryField::Term),
+            "tag" => Some(QueryField::Tag),
+            "group" => Some(QueryField::Group),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Term {
+    /// `field:value`, matched against that field only
+    Field(QueryField, String),
+    /// `"some phrase"`, must appear verbatim (case-insensitive)
+    Phrase(String),
+    /// Plain word, part of the fuzzy text
+    Word(String),
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Clause {
+    pub negated: bool,
+    pub term: Term,
+}
+
+/// A parsed search bar query: every clause must hold (AND), `-` negates a
+/// clause, and the plain words together form the fuzzy-scored text.
+///
+/// ```text
+/// web user:deploy -host:staging tag:prod "eu west" port:22
+/// ```
+#[derive(Debug, Default, PartialEq)]
+pub struct Query {
+    pub clauses: Vec<Clause>,
+}
+
+impl Query {
+    pub fn parse(input: &str) -> Query {
+        let mut clauses = Vec::new();
+        for token in tokenize(input) {
+            let (negated, body) = match token.text.strip_prefix('-') {
+                Some(rest) if !rest.is_empty() && !token.quoted_from_start => (true, rest),
+                _ => (false, token.text.as_str()),
+            };
+            // Explicit AND is accepted; it is the default anyway
+            if !token.quoted && body == "AND" {
+                continue;
+            }
+            let term = match body.split_once(':') {
+                Some((prefix, value)) if !token.quoted_from_start => {
+                    match QueryField::from_prefix(prefix) {
+                        Some(field) => Term::Field(field, value.to_string()),
+                        None if token.quoted => Term::Phrase(body.to_string()),
+                        None => Term::Word(body.to_string()),
+                    }
+                }
+                _ if token.quoted => Term::Phrase(body.to_string()),
+                _ => Term::Word(body.to_string()),
+            };
+            clauses.push(Clause { negated, term });
+        }
+        Query { clauses }
+    }
+
+    /// Plain words to fuzzy-score with, space-joined. Empty if there are none.
+    pub fn fuzzy_text(&self) -> String {
+        self.clauses
+            .iter()
+            .filter(|c| !c.negated)
+            .filter_map(|c| match &c.term {
+                Term::Word(w) => Some(w.as_str()),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Whether `conn` satisfies every field, phrase and negated clause.
+    /// Positive plain words are left to the fuzzy matcher.
+    pub fn matches(&self, conn: &Connection) -> bool {
+        self.clauses.iter().all(|clause| {
+            let hit = match &clause.term {
+                Term::Field(field, value) => field_matches(conn, *field, value),
+                Term::Phrase(p) | Term::Word(p) => contains(&haystack(conn), p),
+            };
+            match (&clause.term, clause.negated) {
+                (Term::Word(_), false) => true,
+                (_, negated) => hit != negated,
+            }
+        })
+    }
+}
+
+/// Which piece of a connection a stretch of the haystack came from.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Part {
+    Group,
+    Name,
+    User,
+    Host,
+    Tag(usize),
+}
+
+/// The pieces the haystack is built from, in order, each with the separator
+/// that precedes it. Host includes the port.
+fn haystack_parts(conn: &Connection) -> Vec<(Part, &'static str, String)> {
+    let mut parts = vec![
+        (Part::Group, "", conn.group_name()),
+        (Part::Name, "  ", conn.name.clone()),
+        (Part::User, "  ", conn.user.clone()),
+        (
+            Part::Host,
+            if conn.user.is_empty() { "" } else { "@" },
+            conn.host_port(),
+        ),
+    ];
+    for (i, tag) in conn.tags.iter().enumerate() {
+        parts.push((Part::Tag(i), "  ", tag.clone()));
+    }
+    parts
+}
+
+/// Text the fuzzy query and phrases are matched against.
+pub fn haystack(conn: &Connection) -> String {
+    haystack_parts(conn)
+        .into_iter()
+        .map(|(_, sep, text)| format!("{}{}", sep, text))
+        .collect()
+}
+
+/// Translate char positions in `haystack(conn)` (as returned by the fuzzy
+/// matcher) into char positions within `part`.
+pub fn part_indices(conn: &Connection, indices: &[usize], part: Part) -> Vec<usize> {
+    let mut offset = 0;
+    for (p, sep, text) in haystack_parts(conn) {
+        offset += sep.chars().count();
+        let len = text.chars().count();
+        if p == part {
+            return indices
+                .iter()
+                .filter(|&&i| i >= offset && i < offset + len)
+                .map(|&i| i - offset)
+                .collect();
+        }
+        offset += len;
+    }
+    vec![]
+}
+
+fn field_matches(conn: &Connection, field: QueryField, value: &str) -> bool {
+    match field {
+        QueryField::Name => contains(&conn.name, value),
+        QueryField::User => contains(&conn.user, value),
+        QueryField::Host => contains(&conn.host, value),
+        QueryField::Port => conn.port.unwrap_or(22).to_string() == value,
+        QueryField::Term => contains(conn.term().unwrap_or_default(), value),
+        QueryField::Tag => conn.has_tag(value),
+        QueryField::Group => contains(&conn.group_name(), value),
+    }
+}
+
+fn contains(hay: &str, needle: &str) -> bool {
+    hay.to_lowercase().contains(&needle.to_lowercase())
+}
+
+struct Token {
+    text: String,
+    /// Some part of the token was in double quotes
+    quoted: bool,
+    /// The token opened with a quote, so `-` and `field:` are literal
+    quoted_from_start: bool,
+}
+
+/// Split on whitespace, keeping double-quoted runs together. Quotes may
+/// start mid-token (`name:"web 01"`) and are removed from the text.
+fn tokenize(input: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut cur: Option<Token> = None;
+    let mut in_quotes = false;
+    for ch in input.chars() {
+        match ch {
+            '"' => {
+                let tok = cur.get_or_insert_with(|| Token {
+                    text: String::new(),
+                    quoted: false,
+                    quoted_from_start: true,
+                });
+                tok.quoted = true;
+                in_quotes = !in_quotes;
+            }
+            c if c.is_whitespace() && !in_quotes => {
+                if let Some(tok) = cur.take() {
+                    tokens.push(tok);
+                }
+            }
+            c => {
+                cur.get_or_insert_with(|| Token {
+                    text: String::new(),
+                    quoted: false,
+                    quoted_from_start: false,
+                })
+                .text
+                .push(c);
+            }
+        }
+    }
+    tokens.extend(cur);
+    tokens.retain(|t| !t.text.is_empty());
+    tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clause(negated: bool, term: Term) -> Clause {
+        Clause { negated, term }
+    }
+
+    fn word(s: &str) -> Term {
+        Term::Word(s.to_string())
+    }
+
+    fn phrase(s: &str) -> Term {
+        Term::Phrase(s.to_string())
+    }
+
+    fn field(f: QueryField, s: &str) -> Term {
+        Term::Field(f, s.to_string())
+    }
+
+    #[test]
+    fn parse() {
+        use QueryField::*;
+        let cases: Vec<(&str, Vec<Clause>)> = vec![
+            ("", vec![]),
+            ("  \"\"  ", vec![]),
+            ("web", vec![clause(false, word("web"))]),
+            ("web  db", vec![clause(false, word("web")), clause(false, word("db"))]),
+            ("user:deploy", vec![clause(false, field(User, "deploy"))]),
+            ("USER:Bob", vec![clause(false, field(User, "Bob"))]),
+            ("-host:staging", vec![clause(true, field(Host, "staging"))]),
+            ("port:", vec![clause(false, field(Port, ""))]),
+            ("\"eu west\"", vec![clause(false, phrase("eu west"))]),
+            // A quote may start mid-token
+            ("name:\"web 01\"", vec![clause(false, field(Name, "web 01"))]),
+            ("-\"eu west\"", vec![clause(true, phrase("eu west"))]),
+            ("-tag:\"a b\"", vec![clause(true, field(Tag, "a b"))]),
+            // A token that opens with a quote is taken literally
+            ("\"-web\"", vec![clause(false, phrase("-web"))]),
+            ("\"user:x\"", vec![clause(false, phrase("user:x"))]),
+            // Unknown prefixes are plain text
+            ("foo:bar", vec![clause(false, word("foo:bar"))]),
+            ("foo:\"a b\"", vec![clause(false, phrase("foo:a b"))]),
+            ("-foo:bar", vec![clause(true, word("foo:bar"))]),
+            // AND is the default and may be spelled out
+            ("web AND db", vec![clause(false, word("web")), clause(false, word("db"))]),
+            ("\"AND\" and", vec![clause(false, phrase("AND")), clause(false, word("and"))]),
+            ("-", vec![clause(false, word("-"))]),
+        ];
+        for (input, want) in cases {
+            assert_eq!(Query::parse(input).clauses, want, "{}", input);
+        }
+    }
+
+    #[test]
+    fn fuzzy_text_is_positive_words() {
+        let q = Query::parse("web -db user:x \"a b\" prod");
+        assert_eq!(q.fuzzy_text(), "web prod");
+    }
+
+    fn web1() -> Connection {
+        let mut c = Connection::default();
+        c.name = "web1".into();
+        c.user = "deploy".into();
+        c.host = "10.0.0.5".into();
+        c.port = Some(2222);
+        c.group = Some("prod/eu".into());
+        c.tags = vec!["Prod".into(), "web".into()];
+        c.set_term(Some("xterm-kitty".into()));
+        c
+    }
+
+    #[test]
+    fn matches() {
+        let conn = web1();
+        let mut plain = Connection::default();
+        plain.name = "db".into();
+        plain.host = "db.example".into();
+
+        let cases = [
+            ("", &conn, true),
+            // Plain words are left to the fuzzy matcher
+            ("nothing", &conn, true),
+            ("-nothing", &conn, true),
+            ("-web", &conn, false),
+            ("user:dep", &conn, true),
+            ("user:DEP", &conn, true),
+            ("user:root", &conn, false),
+            ("-user:deploy", &conn, false),
+            ("host:10.0.", &conn, true),
+            ("name:web", &conn, true),
+            ("port:2222", &conn, true),
+            ("port:22", &conn, false),
+            ("port:22", &plain, true),
+            ("tag:prod", &conn, true),
+            ("tag:pro", &conn, false),
+            ("-tag:prod", &plain, true),
+            ("group:eu", &conn, true),
+            ("group:eu", &plain, false),
+            ("term:kitty", &conn, true),
+            ("term:xterm", &plain, false),
+            ("\"10.0.0\"", &conn, true),
+            ("\"deploy@10.0\"", &conn, true),
+            ("\"web 1\"", &conn, false),
+            ("-\"web1\"", &conn, false),
+            // Every clause must hold
+            ("user:deploy tag:prod -host:staging", &conn, true),
+            ("user:deploy tag:db", &conn, false),
+        ];
+        for (input, c, want) in cases {
+            assert_eq!(Query::parse(input).matches(c), want, "{} on {}", input, c.name);
+        }
+    }
+}
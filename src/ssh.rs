@@ -1,17 +1,269 @@
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use crossterm::{
     event::{DisableMouseCapture, EnableMouseCapture},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use std::{
-    io::{self, Write},
-    process::Command,
+    fs,
+    io::{self, IsTerminal, Read, Write},
+    process::{Command, ExitStatus},
 };
 
-use crate::models::Connection;
+use crate::config::{expand_tilde, home_dir, record_usage};
+use crate::models::{jump_chain, Connection, Hop, Transport};
+use crate::ssh_options::unknown_options;
+
+const DEFAULT_TERM: &str = "xterm-256color";
+
+/// Arguments passed to `ssh` for this connection. `all` is used to resolve
+/// jump hosts by name.
+pub fn ssh_args(conn: &Connection, all: &[Connection]) -> Result<Vec<String>> {
+    let mut args = vec![conn.destination()];
+    args.extend(ssh_flags(conn, all)?);
+    for fwd in &conn.forwards {
+        args.extend([fwd.kind.flag().to_string(), fwd.spec()]);
+    }
+    Ok(args)
+}
+
+/// Port, options, key and jump hosts as ssh flags: what ssh needs to reach
+/// the host, without the destination or forwards.
+fn ssh_flags(conn: &Connection, all: &[Connection]) -> Result<Vec<String>> {
+    let mut args = Vec::new();
+    if let Some(p) = conn.port {
+        args.extend(["-p".to_string(), p.to_string()]);
+    }
+    for opt in ssh_options(conn, all)? {
+        args.extend(["-o".to_string(), opt]);
+    }
+    Ok(args)
+}
+
+/// `Key=Value` options for everything but the port, in the order ssh
+/// should see them.
+fn ssh_options(conn: &Connection, all: &[Connection]) -> Result<Vec<String>> {
+    // ssh keeps the first value it sees, so these win over the ones below
+    let mut opts: Vec<String> = conn
+        .options
+        .iter()
+        .map(|(key, value)| format!("{}={}", key, value))
+        .collect();
+    // TERM already travels with the terminal request
+    for name in conn.env.keys().filter(|k| *k != "TERM") {
+        opts.push(format!("SendEnv={}", name));
+    }
+    opts.extend(identity_options(conn));
+    let hops = jump_chain(conn, all)?;
+    if hops.iter().any(needs_own_settings) {
+        opts.push(format!("ProxyCommand={}", proxy_command(&hops)));
+    } else if !hops.is_empty() {
+        let specs: Vec<String> = hops.iter().map(|h| h.jump_spec()).collect();
+        opts.push(format!("ProxyJump={}", specs.join(",")));
+    }
+    Ok(opts)
+}
+
+fn identity_options(conn: &Connection) -> Vec<String> {
+    match &conn.identity_file {
+        // Only offer this key, not everything loaded in the agent
+        Some(key) => vec![
+            format!("IdentityFile={}", expand_tilde(key).display()),
+            "IdentitiesOnly=yes".to_string(),
+        ],
+        None => vec![],
+    }
+}
+
+/// ProxyJump only carries `user@host:port` for each hop, so a jump host
+/// with its own key or options has to be reached with a ProxyCommand.
+fn needs_own_settings(hop: &Hop) -> bool {
+    matches!(hop, Hop::Connection(c) if c.identity_file.is_some() || !c.options.is_empty())
+}
+
+/// `ssh [hop's settings] -W %h:%p <last hop>` for the ProxyCommand option,
+/// reaching the last hop through a nested ProxyCommand for the ones before.
+/// ssh expands `%` tokens once per level, so everything but this level's
+/// `%h:%p` has its `%` doubled.
+fn proxy_command(hops: &[Hop]) -> String {
+    let Some((last, before)) = hops.split_last() else {
+        return String::new();
+    };
+    let mut args = vec!["ssh".to_string()];
+    let dest = match last {
+        Hop::Connection(c) => {
+            if let Some(p) = c.port {
+                args.extend(["-p".to_string(), p.to_string()]);
+            }
+            let opts = c.options.iter().map(|(key, value)| format!("{}={}", key, value));
+            for opt in opts.chain(identity_options(c)) {
+                args.extend(["-o".to_string(), opt]);
+            }
+            c.destination()
+        }
+        // Same `user@host:port` form as for ProxyJump
+        Hop::Literal(s) => format!("ssh://{}", s),
+    };
+    if !before.is_empty() {
+        args.extend(["-o".to_string(), format!("ProxyCommand={}", proxy_command(before))]);
+    }
+    let mut line: Vec<String> = args.iter().map(|a| shell_quote(a).replace('%', "%%")).collect();
+    line.extend(["-W".to_string(), "%h:%p".to_string(), shell_quote(&dest).replace('%', "%%")]);
+    line.join(" ")
+}
+
+/// mosh runs ssh once to start mosh-server, so the ssh settings go into
+/// `--ssh=`. Forwards cannot be carried over UDP and are left out.
+fn mosh_args(conn: &Connection, all: &[Connection]) -> Result<Vec<String>> {
+    let mut args = Vec::new();
+    let flags = ssh_flags(conn, all)?;
+    if !flags.is_empty() {
+        let ssh: Vec<String> = ["ssh".to_string()]
+            .iter()
+            .chain(&flags)
+            .map(|a| shell_quote(a))
+            .collect();
+        args.push(format!("--ssh={}", ssh.join(" ")));
+    }
+    args.push(conn.destination());
+    Ok(args)
+}
+
+/// et also bootstraps over ssh and takes its settings as `--ssh-option`.
+/// The port given to et itself is etserver's, so the ssh port goes there too.
+fn et_args(conn: &Connection, all: &[Connection]) -> Result<Vec<String>> {
+    let mut args = vec![conn.destination()];
+    let port = conn.port.map(|p| format!("Port={}", p));
+    for opt in port.into_iter().chain(ssh_options(conn, all)?) {
+        args.extend(["--ssh-option".to_string(), opt]);
+    }
+    Ok(args)
+}
+
+/// Exactly what `run_ssh` executes for a connection. Building one has no
+/// side effects, so it can be shown or printed before connecting.
+#[derive(Debug, PartialEq)]
+pub struct SshCommand {
+    /// ssh, or whatever the connection's transport runs
+    pub program: String,
+    /// Set on top of the inherited environment
+    pub env: Vec<(String, String)>,
+    pub args: Vec<String>,
+}
+
+impl SshCommand {
+    /// The transport's command line plus the command to run on the server,
+    /// if any, and the connection's variables with TERM defaulted. ssh gives
+    /// a command no terminal unless asked, so with `tty` set `-t` is added to
+    /// keep interactive programs like tmux working. Leave it unset when the
+    /// output goes to a pipe or file, which a pty would fill with CRLFs.
+    pub fn build(
+        conn: &Connection,
+        all: &[Connection],
+        command: Option<&str>,
+        tty: bool,
+    ) -> Result<SshCommand> {
+        let command = command.map(str::trim).filter(|c| !c.is_empty());
+        let (program, mut args) = match &conn.transport {
+            Transport::Ssh | Transport::Custom(_) => {
+                let program = expand_tilde(conn.transport.ssh_program());
+                (program.display().to_string(), ssh_args(conn, all)?)
+            }
+            Transport::Mosh => ("mosh".to_string(), mosh_args(conn, all)?),
+            Transport::Et => ("et".to_string(), et_args(conn, all)?),
+        };
+        match (&conn.transport, command) {
+            (_, None) => {}
+            (Transport::Ssh | Transport::Custom(_), Some(cmd)) => {
+                if tty {
+                    args.push("-t".to_string());
+                }
+                args.push(cmd.to_string());
+            }
+            // mosh-server execs its arguments, so let a shell split the command
+            (Transport::Mosh, Some(cmd)) => {
+                args.extend(["--", "sh", "-c", cmd].map(str::to_string));
+            }
+            (Transport::Et, Some(cmd)) => {
+                args.extend(["-c".to_string(), cmd.to_string()]);
+            }
+        }
+        let mut env: Vec<(String, String)> = conn
+            .env
+            .iter()
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect();
+        if conn.term().is_none() {
+            env.insert(0, ("TERM".to_string(), DEFAULT_TERM.to_string()));
+        }
+        Ok(SshCommand { program, env, args })
+    }
+
+    /// The command as a line that can be pasted into a POSIX shell.
+    pub fn shell_line(&self) -> String {
+        self.env
+            .iter()
+            .map(|(k, v)| format!("{}={}", k, shell_quote(v)))
+            .chain([shell_quote(&self.program)])
+            .chain(self.args.iter().map(|a| shell_quote(a)))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    pub fn to_command(&self) -> Command {
+        let mut cmd = Command::new(&self.program);
+        cmd.args(&self.args).envs(self.env.iter().map(|(k, v)| (k, v)));
+        cmd
+    }
+}
+
+/// Quote `s` for a POSIX shell, leaving it bare when that is safe.
+pub fn shell_quote(s: &str) -> String {
+    let safe = |c: char| c.is_ascii_alphanumeric() || "@%+=:,./_-".contains(c);
+    if !s.is_empty() && s.chars().all(safe) {
+        s.to_string()
+    } else {
+        format!("'{}'", s.replace('\'', r"'\''"))
+    }
+}
+
+/// One-line rendering of what `run_ssh` would execute with the connection's
+/// own remote command, for display.
+pub fn command_preview(conn: &Connection, all: &[Connection]) -> Result<String> {
+    Ok(SshCommand::build(conn, all, conn.remote_command.as_deref(), true)?.shell_line())
+}
+
+/// Private keys directly under `~/.ssh`, as `~/.ssh/<file>`, sorted.
+pub fn list_private_keys() -> Vec<String> {
+    let Ok(dir) = home_dir().map(|h| h.join(".ssh")) else {
+        return vec![];
+    };
+    let Ok(entries) = fs::read_dir(&dir) else {
+        return vec![];
+    };
+    let mut keys: Vec<String> = entries
+        .filter_map(|e| e.ok())
+        .filter(|e| e.path().is_file())
+        .filter(|e| {
+            // PEM and OpenSSH keys alike start with "-----BEGIN ... PRIVATE KEY-----"
+            let mut head = [0u8; 64];
+            let n = fs::File::open(e.path())
+                .and_then(|mut f| f.read(&mut head))
+                .unwrap_or(0);
+            let head = String::from_utf8_lossy(&head[..n]);
+            head.starts_with("-----BEGIN") && head.contains("PRIVATE KEY")
+        })
+        .map(|e| format!("~/.ssh/{}", e.file_name().to_string_lossy()))
+        .collect();
+    keys.sort();
+    keys
+}
+
+/// Connect interactively, running `command` instead of a login shell when
+/// given (normally the connection's own `remote_command`).
+pub fn run_ssh(conn: &Connection, all: &[Connection], command: Option<&str>) -> Result<()> {
+    let ssh = prepare(conn, all, command, true)?;
 
-pub fn run_ssh(conn: &Connection) -> Result<()> {
     // Leave TUI, run ssh, then come back
     disable_raw_mode()?;
     execute!(
@@ -20,21 +272,17 @@ pub fn run_ssh(conn: &Connection) -> Result<()> {
         DisableMouseCapture
     )?;
 
-    let mut cmd = Command::new("ssh");
-    cmd.arg(format!("{}@{}", conn.user, conn.host));
-    if let Some(p) = conn.port {
-        cmd.args(["-p", &p.to_string()]);
-    }
-    cmd.env(
-        "TERM",
-        conn.term.as_deref().unwrap_or("xterm-256color"),
-    );
+    warn_unknown_options(conn);
 
-    let status = cmd.status().context("failed to launch ssh")?;
+    let status = ssh
+        .to_command()
+        .status()
+        .with_context(|| format!("failed to launch {}", ssh.program))?;
+    let _ = record_usage(&conn.name);
 
     eprintln!(
-        "\n[ jumpseat ] ssh exited with status: {} (press any key to return)",
-        status
+        "\n[ jumpseat ] {} exited with status: {} (press any key to return)",
+        ssh.program, status
     );
     let _ = io::stdout().flush();
 
@@ -49,4 +297,222 @@ pub fn run_ssh(conn: &Connection) -> Result<()> {
     )?;
     enable_raw_mode()?;
     Ok(())
-}
\ No newline at end of file
+}
+
+/// Connect from a plain shell: ssh runs in the foreground of the calling
+/// terminal and its exit status is returned.
+pub fn exec_ssh(conn: &Connection, all: &[Connection], command: Option<&str>) -> Result<ExitStatus> {
+    let tty = io::stdin().is_terminal() && io::stdout().is_terminal();
+    let ssh = prepare(conn, all, command, tty)?;
+    warn_unknown_options(conn);
+    let status = ssh
+        .to_command()
+        .status()
+        .with_context(|| format!("failed to launch {}", ssh.program))?;
+    let _ = record_usage(&conn.name);
+    Ok(status)
+}
+
+/// Build the command, failing early if an identity file is missing, the
+/// connection's own or a jump host's.
+fn prepare(
+    conn: &Connection,
+    all: &[Connection],
+    command: Option<&str>,
+    tty: bool,
+) -> Result<SshCommand> {
+    let ssh = SshCommand::build(conn, all, command, tty)?;
+    let hops = jump_chain(conn, all)?;
+    let hop_keys = hops.iter().filter_map(|h| match h {
+        Hop::Connection(c) => c.identity_file.as_ref(),
+        Hop::Literal(_) => None,
+    });
+    for key in conn.identity_file.iter().chain(hop_keys) {
+        let path = expand_tilde(key);
+        if !path.is_file() {
+            bail!("identity file {} not found", path.display());
+        }
+    }
+    Ok(ssh)
+}
+
+fn warn_unknown_options(conn: &Connection) {
+    let unknown = unknown_options(conn);
+    if !unknown.is_empty() {
+        eprintln!(
+            "[ jumpseat ] warning: ssh may not recognise option(s): {}",
+            unknown.join(", ")
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Forward;
+
+    fn conn(name: &str, user: &str, host: &str) -> Connection {
+        let mut c = Connection::default();
+        c.name = name.to_string();
+        c.user = user.to_string();
+        c.host = host.to_string();
+        c
+    }
+
+    fn strings(items: &[&str]) -> Vec<String> {
+        items.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn ssh_argv_order() {
+        let bastion = conn("bastion", "ops", "bastion.example");
+        let mut web = conn("web", "alice", "web.example");
+        web.port = Some(2222);
+        web.options.insert("ServerAliveInterval".into(), "30".into());
+        web.env.insert("LANG".into(), "C".into());
+        web.identity_file = Some("/keys/web".into());
+        web.jump_hosts = vec!["bastion".into()];
+        web.forwards = vec![Forward::parse("L 8080:localhost:80").unwrap()];
+        let all = [bastion, web.clone()];
+
+        let ssh = SshCommand::build(&web, &all, None, true).unwrap();
+        assert_eq!(ssh.program, "ssh");
+        assert_eq!(
+            ssh.args,
+            strings(&[
+                "alice@web.example",
+                "-p",
+                "2222",
+                "-o",
+                "ServerAliveInterval=30",
+                "-o",
+                "SendEnv=LANG",
+                "-o",
+                "IdentityFile=/keys/web",
+                "-o",
+                "IdentitiesOnly=yes",
+                "-o",
+                "ProxyJump=ops@bastion.example",
+                "-L",
+                "8080:localhost:80",
+            ])
+        );
+    }
+
+    #[test]
+    fn command_gets_tty_only_when_asked() {
+        let web = conn("web", "", "web.example");
+        let all = [web.clone()];
+        let args = |command, tty| SshCommand::build(&web, &all, command, tty).unwrap().args;
+        assert_eq!(args(Some("tmux a"), true), strings(&["web.example", "-t", "tmux a"]));
+        assert_eq!(args(Some(" tmux a "), false), strings(&["web.example", "tmux a"]));
+        // A blank command means a login shell
+        assert_eq!(args(Some("  "), true), strings(&["web.example"]));
+    }
+
+    #[test]
+    fn env_defaults_term() {
+        let mut web = conn("web", "", "web.example");
+        web.env.insert("LANG".into(), "C".into());
+        let all = [web.clone()];
+        let env = SshCommand::build(&web, &all, None, true).unwrap().env;
+        assert_eq!(
+            env,
+            [("TERM", DEFAULT_TERM), ("LANG", "C")].map(|(k, v)| (k.to_string(), v.to_string()))
+        );
+
+        web.set_term(Some("screen".into()));
+        let env = SshCommand::build(&web, &all, None, true).unwrap().env;
+        assert_eq!(
+            env,
+            [("LANG", "C"), ("TERM", "screen")].map(|(k, v)| (k.to_string(), v.to_string()))
+        );
+    }
+
+    #[test]
+    fn mosh_carries_ssh_settings() {
+        let mut web = conn("web", "alice", "web.example");
+        web.transport = Transport::Mosh;
+        web.port = Some(2222);
+        web.identity_file = Some("/my keys/id".into());
+        web.forwards = vec![Forward::parse("D 1080").unwrap()];
+        let all = [web.clone()];
+        let ssh = SshCommand::build(&web, &all, Some("htop"), true).unwrap();
+        assert_eq!(ssh.program, "mosh");
+        assert_eq!(
+            ssh.args,
+            strings(&[
+                "--ssh=ssh -p 2222 -o 'IdentityFile=/my keys/id' -o IdentitiesOnly=yes",
+                "alice@web.example",
+                "--",
+                "sh",
+                "-c",
+                "htop",
+            ])
+        );
+    }
+
+    #[test]
+    fn et_takes_ssh_options() {
+        let mut web = conn("web", "alice", "web.example");
+        web.transport = Transport::Et;
+        web.port = Some(2222);
+        web.identity_file = Some("/keys/web".into());
+        let all = [web.clone()];
+        let ssh = SshCommand::build(&web, &all, Some("htop"), true).unwrap();
+        assert_eq!(ssh.program, "et");
+        assert_eq!(
+            ssh.args,
+            strings(&[
+                "alice@web.example",
+                "--ssh-option",
+                "Port=2222",
+                "--ssh-option",
+                "IdentityFile=/keys/web",
+                "--ssh-option",
+                "IdentitiesOnly=yes",
+                "-c",
+                "htop",
+            ])
+        );
+    }
+
+    #[test]
+    fn jump_host_with_key_uses_proxy_command() {
+        let mut bastion = conn("bastion", "ops", "bastion.example");
+        bastion.identity_file = Some("/keys/bastion".into());
+        let mut inner = conn("inner", "", "10.0.0.1");
+        inner.port = Some(2200);
+        inner.jump_hosts = vec!["bastion".into()];
+        let mut app = conn("app", "deploy", "10.0.0.7");
+        app.jump_hosts = vec!["inner".into()];
+        let all = [bastion, inner, app.clone()];
+
+        let args = SshCommand::build(&app, &all, None, true).unwrap().args;
+        assert_eq!(
+            args,
+            strings(&[
+                "deploy@10.0.0.7",
+                "-o",
+                "ProxyCommand=ssh -p 2200 -o 'ProxyCommand=ssh -o IdentityFile=/keys/bastion \
+                 -o IdentitiesOnly=yes -W %%h:%%p ops@bastion.example' -W %h:%p 10.0.0.1",
+            ])
+        );
+    }
+
+    #[test]
+    fn quoting() {
+        assert_eq!(shell_quote("web-1.example:22"), "web-1.example:22");
+        assert_eq!(shell_quote(""), "''");
+        assert_eq!(shell_quote("a b"), "'a b'");
+        assert_eq!(shell_quote("it's"), r"'it'\''s'");
+        assert_eq!(shell_quote("$HOME"), "'$HOME'");
+
+        let ssh = SshCommand {
+            program: "ssh".into(),
+            env: vec![("TERM".into(), "xterm 256".into())],
+            args: strings(&["web", "-t", "echo 'hi'"]),
+        };
+        assert_eq!(ssh.shell_line(), r"TERM='xterm 256' ssh web -t 'echo '\''hi'\'''");
+    }
+}
@@ -0,0 +1,581 @@
+use anyhow::{bail, Context, Result};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::config::{expand_tilde, home_dir, write_atomic};
+use crate::models::{Connection, Forward, ForwardKind};
+use crate::ssh_options::canonical_name;
+
+// OpenSSH refuses to nest includes deeper than this
+const MAX_INCLUDE_DEPTH: usize = 16;
+
+pub fn default_ssh_config_path() -> Result<PathBuf> {
+    Ok(home_dir()?.join(".ssh").join("config"))
+}
+
+/// Read an OpenSSH client config and turn every concrete `Host` alias into a
+/// `Connection`. Wildcard / negated patterns and `Match` blocks are skipped.
+/// Also returns a warning per host for forwards it cannot represent, such as
+/// Unix socket forwards, which are left out.
+pub fn load_ssh_config(path: &Path) -> Result<(Vec<Connection>, Vec<String>)> {
+    let mut conns = Vec::new();
+    let mut warnings = Vec::new();
+    parse_file(path, 0, &mut conns, &mut warnings)?;
+    for c in conns.iter_mut() {
+        if c.host.is_empty() {
+            c.host = c.name.clone();
+        }
+        // Drop what `render_ssh_config` adds by itself, so that exported
+        // hosts import back unchanged
+        let is_yes = |c: &Connection, key| {
+            c.options.get(key).is_some_and(|v| v.eq_ignore_ascii_case("yes"))
+        };
+        if c.identity_file.is_some() && is_yes(c, "IdentitiesOnly") {
+            c.options.remove("IdentitiesOnly");
+        }
+        if c.remote_command.is_some() && is_yes(c, "RequestTTY") {
+            c.options.remove("RequestTTY");
+        }
+    }
+    Ok((conns, warnings))
+}
+
+fn parse_file(
+    path: &Path,
+    depth: usize,
+    conns: &mut Vec<Connection>,
+    warnings: &mut Vec<String>,
+) -> Result<()> {
+    let data = fs::read_to_string(path)
+        .with_context(|| format!("cannot read {}", path.display()))?;
+
+    // Indices into `conns` that the current Host block applies to
+    let mut current: Vec<usize> = Vec::new();
+
+    for line in data.lines() {
+        let Some((key, args)) = split_line(line) else {
+            continue;
+        };
+        match key.to_ascii_lowercase().as_str() {
+            "host" => {
+                current.clear();
+                for alias in args {
+                    if is_pattern(&alias) {
+                        continue;
+                    }
+                    let i = match conns.iter().position(|c| c.name == alias) {
+                        Some(i) => i,
+                        None => {
+                            let mut conn = Connection::default();
+                            conn.name = alias.clone();
+                            conns.push(conn);
+                            conns.len() - 1
+                        }
+                    };
+                    current.push(i);
+                }
+            }
+            "match" => {
+                current.clear();
+            }
+            "include" => {
+                if depth >= MAX_INCLUDE_DEPTH {
+                    continue;
+                }
+                for pattern in args {
+                    for file in resolve_include(&pattern)? {
+                        parse_file(&file, depth + 1, conns, warnings)?;
+                    }
+                }
+            }
+            kw @ ("localforward" | "remoteforward" | "dynamicforward") => {
+                // Forwards under `Host *` or `Match` are not imported
+                if current.is_empty() {
+                    continue;
+                }
+                let kind = match kw {
+                    "localforward" => ForwardKind::Local,
+                    "remoteforward" => ForwardKind::Remote,
+                    _ => ForwardKind::Dynamic,
+                };
+                // Unlike other keywords, every forward line counts
+                match Forward::parse_spec(kind, &args.join(":")) {
+                    Ok(fwd) => {
+                        for &i in &current {
+                            conns[i].forwards.push(fwd.clone());
+                        }
+                    }
+                    Err(e) => {
+                        for &i in &current {
+                            warnings.push(format!(
+                                "{}: skipped {} {} ({:#})",
+                                conns[i].name,
+                                key,
+                                args.join(" "),
+                                e
+                            ));
+                        }
+                    }
+                }
+            }
+            other => {
+                let Some(value) = args.first() else {
+                    continue;
+                };
+                // Like ssh itself, the first value seen for a keyword wins
+                for &i in &current {
+                    let c = &mut conns[i];
+                    match other {
+                        "hostname" if c.host.is_empty() => c.host = value.clone(),
+                        "user" if c.user.is_empty() => c.user = value.clone(),
+                        "port" if c.port.is_none() => {
+                            c.port = Some(value.parse().with_context(|| {
+                                format!("invalid Port '{}' for host {}", value, c.name)
+                            })?);
+                        }
+                        "identityfile" if c.identity_file.is_none() => {
+                            c.identity_file = Some(value.clone());
+                        }
+                        "proxyjump"
+                            if c.jump_hosts.is_empty() && !value.eq_ignore_ascii_case("none") =>
+                        {
+                            c.jump_hosts = value.split(',').map(str::to_string).collect();
+                        }
+                        "remotecommand" if c.remote_command.is_none() => {
+                            c.remote_command = Some(args.join(" "));
+                        }
+                        "setenv" if c.env.is_empty() => {
+                            for (name, value) in args.iter().filter_map(|a| a.split_once('=')) {
+                                c.env.insert(name.to_string(), value.to_string());
+                            }
+                        }
+                        "hostname" | "user" | "port" | "identityfile" | "proxyjump"
+                        | "remotecommand" | "setenv" => {}
+                        _ => {
+                            if let Some(name) = canonical_name(other) {
+                                c.options
+                                    .entry(name.to_string())
+                                    .or_insert_with(|| args.join(" "));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Split a config line into its keyword and arguments, honouring both
+/// `Key value` and `Key=value` forms and double-quoted arguments.
+fn split_line(line: &str) -> Option<(String, Vec<String>)> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+    let end = line
+        .find(|c: char| c.is_whitespace() || c == '=')
+        .unwrap_or(line.len());
+    let key = line[..end].to_string();
+    let rest = line[end..].trim_start();
+    let rest = rest.strip_prefix('=').unwrap_or(rest);
+
+    let mut args = Vec::new();
+    let mut cur = String::new();
+    let mut in_quotes = false;
+    let mut has_arg = false;
+    for ch in rest.chars() {
+        match ch {
+            '"' => {
+                in_quotes = !in_quotes;
+                has_arg = true;
+            }
+            '#' if !in_quotes && !has_arg => break,
+            c if c.is_whitespace() && !in_quotes => {
+                if has_arg {
+                    args.push(std::mem::take(&mut cur));
+                    has_arg = false;
+                }
+            }
+            c => {
+                cur.push(c);
+                has_arg = true;
+            }
+        }
+    }
+    if has_arg {
+        args.push(cur);
+    }
+    Some((key, args))
+}
+
+fn is_pattern(alias: &str) -> bool {
+    alias.contains(['*', '?', '!'])
+}
+
+/// Expand an `Include` argument to the files it names. Relative paths are
+/// taken from `~/.ssh`, and `*` / `?` are supported in the file name.
+fn resolve_include(pattern: &str) -> Result<Vec<PathBuf>> {
+    let mut path = expand_tilde(pattern);
+    if path.is_relative() {
+        path = home_dir()?.join(".ssh").join(path);
+    }
+    let Some(file_pattern) = path.file_name().and_then(|f| f.to_str()) else {
+        return Ok(vec![]);
+    };
+    if !is_pattern(file_pattern) {
+        return Ok(if path.is_file() { vec![path] } else { vec![] });
+    }
+
+    let dir = path.parent().unwrap_or(Path::new("."));
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Ok(vec![]);
+    };
+    let mut files: Vec<PathBuf> = entries
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.is_file())
+        .filter(|p| {
+            p.file_name()
+                .and_then(|f| f.to_str())
+                .is_some_and(|f| wildcard_match(file_pattern, f))
+        })
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    fn go(p: &[char], t: &[char]) -> bool {
+        match (p.first(), t.first()) {
+            (None, None) => true,
+            (Some('*'), _) => go(&p[1..], t) || (!t.is_empty() && go(p, &t[1..])),
+            (Some('?'), Some(_)) => go(&p[1..], &t[1..]),
+            (Some(a), Some(b)) if a == b => go(&p[1..], &t[1..]),
+            _ => false,
+        }
+    }
+    let p: Vec<char> = pattern.chars().collect();
+    let t: Vec<char> = text.chars().collect();
+    go(&p, &t)
+}
+
+const MANAGED_BEGIN: &str = "# >>> jumpseat managed hosts >>>";
+const MANAGED_END: &str = "# <<< jumpseat managed hosts <<<";
+
+/// Render a `Host` stanza per connection, usable with plain `ssh <name>`.
+pub fn render_ssh_config(conns: &[Connection]) -> String {
+    let mut out = String::new();
+    for (i, c) in conns.iter().enumerate() {
+        if i > 0 {
+            out.push('\n');
+        }
+        out.push_str(&format!("Host {}\n", quote_arg(&c.name)));
+        out.push_str(&format!("    HostName {}\n", c.host));
+        if !c.user.is_empty() {
+            out.push_str(&format!("    User {}\n", quote_arg(&c.user)));
+        }
+        if let Some(p) = c.port {
+            out.push_str(&format!("    Port {}\n", p));
+        }
+        if let Some(key) = &c.identity_file {
+            out.push_str(&format!("    IdentityFile {}\n", quote_arg(key)));
+            if !c.options.contains_key("IdentitiesOnly") {
+                out.push_str("    IdentitiesOnly yes\n");
+            }
+        }
+        if !c.jump_hosts.is_empty() {
+            out.push_str(&format!("    ProxyJump {}\n", c.jump_hosts.join(",")));
+        }
+        if let Some(cmd) = &c.remote_command {
+            out.push_str(&format!("    RemoteCommand {}\n", cmd));
+            if !c.options.contains_key("RequestTTY") {
+                out.push_str("    RequestTTY yes\n");
+            }
+        }
+        let env: Vec<String> = c
+            .env
+            .iter()
+            .filter(|(k, _)| *k != "TERM")
+            .map(|(k, v)| format!("{}={}", k, quote_arg(v)))
+            .collect();
+        // Only the first SetEnv line counts, so an explicit one wins
+        if !env.is_empty() && !c.options.contains_key("SetEnv") {
+            out.push_str(&format!("    SetEnv {}\n", env.join(" ")));
+        }
+        for (key, value) in &c.options {
+            out.push_str(&format!("    {} {}\n", key, value));
+        }
+        for fwd in &c.forwards {
+            let keyword = match fwd.kind {
+                ForwardKind::Local => "LocalForward",
+                ForwardKind::Remote => "RemoteForward",
+                ForwardKind::Dynamic => "DynamicForward",
+            };
+            // The config file separates the listen side from the target with a space
+            match fwd.target_spec() {
+                Some(target) => out.push_str(&format!(
+                    "    {} {} {}\n",
+                    keyword,
+                    fwd.listen_spec(),
+                    target
+                )),
+                None => out.push_str(&format!("    {} {}\n", keyword, fwd.listen_spec())),
+            }
+        }
+    }
+    out
+}
+
+/// Write the rendered stanzas into a marker-delimited section of `path`,
+/// replacing a previous section in place or adding one. Everything outside
+/// the markers is left untouched.
+pub fn write_managed_section(path: &Path, conns: &[Connection]) -> Result<()> {
+    let existing = if path.exists() {
+        fs::read_to_string(path).with_context(|| format!("cannot read {}", path.display()))?
+    } else {
+        String::new()
+    };
+    let section = format!("{}\n{}{}\n", MANAGED_BEGIN, render_ssh_config(conns), MANAGED_END);
+
+    let updated = match (existing.find(MANAGED_BEGIN), existing.find(MANAGED_END)) {
+        (Some(start), Some(end)) if start < end => {
+            let mut tail = &existing[end + MANAGED_END.len()..];
+            tail = tail.strip_prefix('\n').unwrap_or(tail);
+            format!("{}{}{}", &existing[..start], section, tail)
+        }
+        // ssh takes the first value it reads for each option, so a new section
+        // goes before the first Host/Match block, ahead of any `Host *`
+        // defaults. Global lines above it stay global.
+        (None, None) => match first_block_start(&existing) {
+            Some(at) => format!("{}{}\n{}", &existing[..at], section, &existing[at..]),
+            None => {
+                let mut s = existing;
+                if !s.is_empty() && !s.ends_with('\n') {
+                    s.push('\n');
+                }
+                if !s.is_empty() {
+                    s.push('\n');
+                }
+                s.push_str(&section);
+                s
+            }
+        },
+        _ => bail!(
+            "{} has an unbalanced jumpseat section; fix the markers by hand",
+            path.display()
+        ),
+    };
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    write_atomic(path, updated.as_bytes())
+}
+
+/// Byte offset of the first `Host` or `Match` line, or of the comment lines
+/// directly above it.
+fn first_block_start(text: &str) -> Option<usize> {
+    let mut offset = 0;
+    let mut comments = None;
+    for line in text.split_inclusive('\n') {
+        match split_line(line) {
+            Some((key, _))
+                if key.eq_ignore_ascii_case("host") || key.eq_ignore_ascii_case("match") =>
+            {
+                return Some(comments.unwrap_or(offset));
+            }
+            None if line.trim_start().starts_with('#') => {
+                comments.get_or_insert(offset);
+            }
+            _ => comments = None,
+        }
+        offset += line.len();
+    }
+    None
+}
+
+fn quote_arg(s: &str) -> String {
+    if s.contains(char::is_whitespace) {
+        format!("\"{}\"", s)
+    } else {
+        s.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::merge_connections;
+    use crate::models::Transport;
+
+    /// An empty directory under the system temp dir, unique to the test.
+    fn scratch(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("jumpseat-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn conn(name: &str, user: &str) -> Connection {
+        let mut c = Connection::default();
+        c.name = name.to_string();
+        c.user = user.to_string();
+        c.host = format!("{}.example", name);
+        c
+    }
+
+    /// `name user@host:port`, plus ` key=…` when an identity file is set.
+    fn describe(c: &Connection) -> String {
+        let mut s = format!("{} {}", c.name, c.jump_spec());
+        if let Some(key) = &c.identity_file {
+            s.push_str(&format!(" key={}", key));
+        }
+        s
+    }
+
+    fn load(path: &Path) -> Vec<String> {
+        load_ssh_config(path).unwrap().0.iter().map(describe).collect()
+    }
+
+    #[test]
+    fn parse_hosts() {
+        let cases: [(&str, &[&str]); 7] = [
+            // Like ssh, the first value for a keyword wins, across blocks too
+            (
+                "Host a\n  HostName one\n  HostName two\nHost a\n  HostName three\n  User u\n",
+                &["a u@one"],
+            ),
+            // Key=value, with or without spaces around the '='
+            ("Host a\nHostName=h.example\nPort = 2222\nUser= bob\n", &["a bob@h.example:2222"]),
+            // Quoted arguments keep their spaces
+            (
+                "Host \"my host\"\n  HostName h\n  IdentityFile \"~/My Keys/id\"\n",
+                &["my host h key=~/My Keys/id"],
+            ),
+            // Every alias on a Host line gets the block's settings
+            ("Host a b\n  HostName h\n  User u\n", &["a u@h", "b u@h"]),
+            // Patterns are skipped, and so are their settings
+            ("Host *\n  User root\nHost !a web-? db*\n  Port 1\nHost a\n", &["a a"]),
+            // A Match block applies to no imported host
+            ("Host a\n  HostName h\nMatch host a\n  User m\n  HostName other\n", &["a h"]),
+            // Comments, whole-line and trailing
+            ("# hosts\nHost a # first\n  HostName h # note\n", &["a h"]),
+        ];
+        let dir = scratch("parse");
+        let path = dir.join("config");
+        for (text, want) in cases {
+            fs::write(&path, text).unwrap();
+            assert_eq!(load(&path), want, "{:?}", text);
+        }
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn include_globs_and_depth_limit() {
+        let dir = scratch("include");
+        fs::create_dir(dir.join("conf.d")).unwrap();
+        fs::write(dir.join("conf.d/b.conf"), "Host b\n").unwrap();
+        fs::write(dir.join("conf.d/a.conf"), "Host a\n  User from-a\n").unwrap();
+        fs::write(dir.join("conf.d/c.txt"), "Host c\n").unwrap();
+        // Includes itself, which has to stop at MAX_INCLUDE_DEPTH
+        let looped = dir.join("loop");
+        fs::write(&looped, format!("Include {}\nHost loop\n", looped.display())).unwrap();
+        let path = dir.join("config");
+        fs::write(
+            &path,
+            format!(
+                "Include {}/conf.d/*.conf\nInclude {}\nHost a\n  User main\n",
+                dir.display(),
+                looped.display()
+            ),
+        )
+        .unwrap();
+
+        // Files in name order, each where its Include line is
+        assert_eq!(load(&path), ["a from-a@a", "b b", "loop loop"]);
+
+        // A chain of includes is read down to MAX_INCLUDE_DEPTH and no further
+        let depth = MAX_INCLUDE_DEPTH + 3;
+        for n in 0..depth {
+            let next = dir.join(format!("chain{}", n + 1));
+            let text = format!("Include {}\nHost h{}\n", next.display(), n);
+            fs::write(dir.join(format!("chain{}", n)), text).unwrap();
+        }
+        let hosts = load(&dir.join("chain0"));
+        assert_eq!(hosts.len(), MAX_INCLUDE_DEPTH + 1);
+        assert_eq!(hosts[0], format!("h{0} h{0}", MAX_INCLUDE_DEPTH));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn export_then_import_matches() {
+        let mut db = conn("db2", "pg");
+        db.port = Some(5433);
+        db.group = Some("prod/eu".into());
+        db.tags = vec!["prod".into(), "db".into()];
+        db.identity_file = Some("~/.ssh/id_db".into());
+        db.jump_hosts = vec!["bastion".into(), "ops@gw.example:2222".into()];
+        db.transport = Transport::Mosh;
+        db.set_term(Some("screen".into()));
+        db.env.insert("LANG".into(), "C".into());
+        db.env.insert("GREETING".into(), "hello there".into());
+        db.remote_command = Some("tmux new -A -s main".into());
+        db.options.insert("ServerAliveInterval".into(), "30".into());
+        db.forwards = vec![
+            Forward::parse_spec(ForwardKind::Local, "127.0.0.1:8080:localhost:80").unwrap(),
+            Forward::parse_spec(ForwardKind::Dynamic, "1080").unwrap(),
+        ];
+        let mut spaced = conn("my host", "");
+        spaced.host = "10.0.0.3".into();
+        spaced.tags = vec!["home".into()];
+        let mut own = conn("own", "me");
+        own.identity_file = Some("~/.ssh/id_own".into());
+        own.options.insert("IdentitiesOnly".into(), "no".into());
+        own.options.insert("RequestTTY".into(), "force".into());
+        own.remote_command = Some("htop".into());
+        let conns = vec![conn("bastion", "ops"), db, spaced, own];
+
+        let dir = scratch("round-trip");
+        let path = dir.join("config");
+        fs::write(&path, render_ssh_config(&conns)).unwrap();
+        let (incoming, warnings) = load_ssh_config(&path).unwrap();
+        fs::remove_dir_all(dir).unwrap();
+
+        assert!(warnings.is_empty(), "{:?}", warnings);
+        let report = merge_connections(&mut conns.clone(), incoming);
+        assert!(report.added.is_empty(), "{:?}", report.added);
+        assert!(report.conflicts.is_empty(), "{:?}", report.conflicts);
+        assert_eq!(report.skipped.len(), conns.len());
+    }
+
+    #[test]
+    fn managed_section_goes_before_host_blocks() {
+        let dir = scratch("placement");
+        let path = dir.join("config");
+        let conns = [conn("web", "alice")];
+        let cases = [
+            // Global lines stay above it, a comment stays with its Host
+            (
+                "Compression yes\n\n# defaults\nHost *\n    User bob\n",
+                "Compression yes\n\n@\n# defaults\nHost *\n    User bob\n",
+            ),
+            ("Match all\n    User bob\n", "@\nMatch all\n    User bob\n"),
+            ("Compression yes", "Compression yes\n\n@"),
+            ("", "@"),
+        ];
+        let section = format!("{}\n{}{}\n", MANAGED_BEGIN, render_ssh_config(&conns), MANAGED_END);
+        for (before, want) in cases {
+            fs::write(&path, before).unwrap();
+            write_managed_section(&path, &conns).unwrap();
+            let want = want.replace('@', &section);
+            assert_eq!(fs::read_to_string(&path).unwrap(), want, "{:?}", before);
+            // Writing again replaces the section where it is
+            write_managed_section(&path, &conns).unwrap();
+            assert_eq!(fs::read_to_string(&path).unwrap(), want, "{:?}", before);
+        }
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,176 @@
+use anyhow::{bail, Result};
+
+use crate::models::Connection;
+
+/// Keywords from ssh_config(5) that can be passed to ssh as `-o Key=Value`.
+const KNOWN_OPTIONS: &[&str] = &[
+    "AddKeysToAgent",
+    "AddressFamily",
+    "BatchMode",
+    "BindAddress",
+    "BindInterface",
+    "CanonicalDomains",
+    "CanonicalizeFallbackLocal",
+    "CanonicalizeHostname",
+    "CanonicalizeMaxDots",
+    "CanonicalizePermittedCNAMEs",
+    "CASignatureAlgorithms",
+    "CertificateFile",
+    "ChannelTimeout",
+    "CheckHostIP",
+    "Ciphers",
+    "ClearAllForwardings",
+    "Compression",
+    "ConnectionAttempts",
+    "ConnectTimeout",
+    "ControlMaster",
+    "ControlPath",
+    "ControlPersist",
+    "DynamicForward",
+    "EnableEscapeCommandline",
+    "EnableSSHKeysign",
+    "EscapeChar",
+    "ExitOnForwardFailure",
+    "FingerprintHash",
+    "ForkAfterAuthentication",
+    "ForwardAgent",
+    "ForwardX11",
+    "ForwardX11Timeout",
+    "ForwardX11Trusted",
+    "GatewayPorts",
+    "GlobalKnownHostsFile",
+    "GSSAPIAuthentication",
+    "GSSAPIDelegateCredentials",
+    "HashKnownHosts",
+    "HostbasedAcceptedAlgorithms",
+    "HostbasedAuthentication",
+    "HostKeyAlgorithms",
+    "HostKeyAlias",
+    "HostName",
+    "IdentitiesOnly",
+    "IdentityAgent",
+    "IdentityFile",
+    "IgnoreUnknown",
+    "IPQoS",
+    "KbdInteractiveAuthentication",
+    "KbdInteractiveDevices",
+    "KexAlgorithms",
+    "KnownHostsCommand",
+    "LocalCommand",
+    "LocalForward",
+    "LogLevel",
+    "LogVerbose",
+    "MACs",
+    "NoHostAuthenticationForLocalhost",
+    "NumberOfPasswordPrompts",
+    "ObscureKeystrokeTiming",
+    "PasswordAuthentication",
+    "PermitLocalCommand",
+    "PermitRemoteOpen",
+    "PKCS11Provider",
+    "Port",
+    "PreferredAuthentications",
+    "ProxyCommand",
+    "ProxyJump",
+    "ProxyUseFdpass",
+    "PubkeyAcceptedAlgorithms",
+    "PubkeyAuthentication",
+    "RekeyLimit",
+    "RemoteCommand",
+    "RemoteForward",
+    "RequestTTY",
+    "RequiredRSASize",
+    "RevokedHostKeys",
+    "SecurityKeyProvider",
+    "SendEnv",
+    "ServerAliveCountMax",
+    "ServerAliveInterval",
+    "SessionType",
+    "SetEnv",
+    "StdinNull",
+    "StreamLocalBindMask",
+    "StreamLocalBindUnlink",
+    "StrictHostKeyChecking",
+    "SyslogFacility",
+    "TCPKeepAlive",
+    "Tag",
+    "Tunnel",
+    "TunnelDevice",
+    "UpdateHostKeys",
+    "User",
+    "UserKnownHostsFile",
+    "VerifyHostKeyDNS",
+    "VisualHostKey",
+    "XAuthLocation",
+];
+
+/// Options that have their own connection field and would clash with it.
+const FIELD_OPTIONS: &[(&str, &str)] = &[
+    ("HostName", "Host"),
+    ("User", "User"),
+    ("Port", "Port"),
+    ("IdentityFile", "Key"),
+    ("ProxyJump", "Via"),
+    ("LocalForward", "Fwd"),
+    ("RemoteForward", "Fwd"),
+    ("DynamicForward", "Fwd"),
+    ("RemoteCommand", "Cmd"),
+];
+
+/// The ssh_config(5) spelling of `key`, matched case-insensitively like ssh
+/// does. None if OpenSSH does not know the option.
+pub fn canonical_name(key: &str) -> Option<&'static str> {
+    KNOWN_OPTIONS
+        .iter()
+        .find(|k| k.eq_ignore_ascii_case(key))
+        .copied()
+}
+
+/// Parse `Key=Value` or `Key Value` into its parts, using the canonical
+/// spelling for known keys. Unknown keys are accepted as written; see
+/// `canonical_name` to warn about them.
+pub fn parse_option(item: &str) -> Result<(String, String)> {
+    let item = item.trim();
+    let (key, value) = match item.split_once(['=', ' ', '\t']) {
+        Some((k, v)) => (k.trim(), v.trim().trim_start_matches('=').trim()),
+        None => bail!("expected Key=Value, got '{}'", item),
+    };
+    if key.is_empty() || !key.chars().all(|c| c.is_ascii_alphanumeric()) {
+        bail!("invalid option name '{}'", key);
+    }
+    if value.is_empty() {
+        bail!("{} has no value", key);
+    }
+    if let Some((name, field)) = FIELD_OPTIONS.iter().find(|(k, _)| k.eq_ignore_ascii_case(key)) {
+        bail!("set {} with the {} field", name, field);
+    }
+    let key = canonical_name(key).map(str::to_string).unwrap_or_else(|| key.to_string());
+    Ok((key, value.to_string()))
+}
+
+/// Parse `NAME=value` for a connection's environment. TERM has a field of
+/// its own.
+pub fn parse_env(item: &str) -> Result<(String, String)> {
+    let Some((name, value)) = item.trim().split_once('=') else {
+        bail!("expected NAME=value, got '{}'", item.trim());
+    };
+    let name = name.trim();
+    let valid = name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !valid {
+        bail!("invalid variable name '{}'", name);
+    }
+    if name == "TERM" {
+        bail!("set TERM with the Term field");
+    }
+    Ok((name.to_string(), value.trim().to_string()))
+}
+
+/// Keys in `conn.options` that OpenSSH does not recognise.
+pub fn unknown_options(conn: &Connection) -> Vec<&str> {
+    conn.options
+        .keys()
+        .filter(|k| canonical_name(k).is_none())
+        .map(String::as_str)
+        .collect()
+}
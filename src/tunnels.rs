@@ -0,0 +1,122 @@
+use anyhow::{bail, Context, Result};
+use std::{
+    io::{BufRead, BufReader},
+    process::{Child, Command, Stdio},
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::config::expand_tilde;
+use crate::models::Connection;
+use crate::ssh::ssh_args;
+
+pub enum TunnelStatus {
+    Running,
+    /// ssh exited on its own; holds a description of how
+    Exited(String),
+    /// Stopped from the UI
+    Stopped,
+}
+
+/// A forward-only `ssh -N` running in the background.
+pub struct Tunnel {
+    /// Connection name, looked up again on restart
+    pub name: String,
+    /// Forwards as they were when started, for display
+    pub forwards: Vec<String>,
+    pub started: Instant,
+    pub status: TunnelStatus,
+    child: Option<Child>,
+    /// Last line ssh wrote to stderr, to explain failures
+    last_error: Arc<Mutex<String>>,
+}
+
+impl Tunnel {
+    pub fn start(conn: &Connection, all: &[Connection]) -> Result<Tunnel> {
+        if conn.forwards.is_empty() {
+            bail!("{} has no port forwards to hold open", conn.name);
+        }
+        let mut cmd = Command::new(expand_tilde(conn.transport.ssh_program()));
+        cmd.args(ssh_args(conn, all)?)
+            // No remote command, fail instead of running without the
+            // forwards, and never prompt: there is no terminal to prompt on
+            .args(["-N", "-o", "ExitOnForwardFailure=yes", "-o", "BatchMode=yes"])
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped());
+        let mut child = cmd.spawn().context("failed to launch ssh")?;
+
+        // Drain stderr so ssh never blocks on a full pipe
+        let last_error = Arc::new(Mutex::new(String::new()));
+        if let Some(stderr) = child.stderr.take() {
+            let last = Arc::clone(&last_error);
+            thread::spawn(move || {
+                for line in BufReader::new(stderr).lines().map_while(|l| l.ok()) {
+                    if !line.trim().is_empty() {
+                        if let Ok(mut l) = last.lock() {
+                            *l = line;
+                        }
+                    }
+                }
+            });
+        }
+
+        Ok(Tunnel {
+            name: conn.name.clone(),
+            forwards: conn.forwards.iter().map(|f| f.to_string()).collect(),
+            started: Instant::now(),
+            status: TunnelStatus::Running,
+            child: Some(child),
+            last_error,
+        })
+    }
+
+    /// Notice if ssh has exited since the last call.
+    pub fn poll(&mut self) {
+        let Some(child) = &mut self.child else {
+            return;
+        };
+        if let Ok(Some(status)) = child.try_wait() {
+            let last = self.last_error.lock().map(|l| l.clone()).unwrap_or_default();
+            self.status = TunnelStatus::Exited(if last.is_empty() {
+                status.to_string()
+            } else {
+                format!("{}: {}", status, last)
+            });
+            self.child = None;
+        }
+    }
+
+    pub fn stop(&mut self) {
+        if let Some(mut child) = self.child.take() {
+            let _ = child.kill();
+            let _ = child.wait();
+            self.status = TunnelStatus::Stopped;
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        matches!(self.status, TunnelStatus::Running)
+    }
+
+    pub fn uptime(&self) -> Duration {
+        self.started.elapsed()
+    }
+}
+
+impl Drop for Tunnel {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// `1h02m`, `3m07s`, `12s`
+pub fn format_uptime(d: Duration) -> String {
+    let s = d.as_secs();
+    match s {
+        s if s >= 3600 => format!("{}h{:02}m", s / 3600, s / 60 % 60),
+        s if s >= 60 => format!("{}m{:02}s", s / 60, s % 60),
+        s => format!("{}s", s),
+    }
+}
@@ -1,4 +1,4 @@
 pub mod renderer;
 pub mod widgets;
 
-pub use renderer::draw_ui;
\ No newline at end of file
+pub use renderer::{draw_pick_ui, draw_ui};
\ No newline at end of file
@@ -3,12 +3,16 @@ use ratatui::{
     prelude::Rect,
     style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, BorderType, Borders, Clear, List, ListItem, Paragraph},
+    widgets::{Block, BorderType, Borders, Clear, List, ListItem, Paragraph, Wrap},
     Frame,
 };
 
-use crate::app::{AppState, Mode};
-use super::widgets::{centered_rect, tui_list_state};
+use crate::app::{AppState, Mode, Row};
+use crate::models::jump_chain;
+use crate::query::{part_indices, Part};
+use crate::ssh::command_preview;
+use crate::tunnels::{format_uptime, TunnelStatus};
+use super::widgets::{centered_box, centered_rect, tui_list_state};
 
 pub fn draw_ui(f: &mut Frame, app: &mut AppState) {
     let chunks = Layout::default()
@@ -16,29 +20,67 @@ pub fn draw_ui(f: &mut Frame, app: &mut AppState) {
         .constraints([
             Constraint::Length(3),
             Constraint::Min(1),
-            Constraint::Length(2),
+            Constraint::Length(3),
         ])
         .split(f.area());
 
     draw_search_bar(f, app, chunks[0]);
-    draw_connections_list(f, app, chunks[1]);
+    if app.tunnels.is_empty() {
+        draw_connections_list(f, app, chunks[1]);
+    } else {
+        let height = (app.tunnels.len() as u16 + 2).min(8);
+        let middle = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(1), Constraint::Length(height)])
+            .split(chunks[1]);
+        draw_connections_list(f, app, middle[0]);
+        draw_tunnels_panel(f, app, middle[1]);
+    }
     draw_footer(f, app, chunks[2]);
     
     // Draw dialogs on top
-    if app.mode == Mode::Add || app.pending_delete || app.show_help {
-        let area = centered_rect(80, 40, f.area());
+    if app.mode == Mode::Add || app.mode == Mode::Edit {
+        // Fields, the blank lines around them, the preview and the borders
+        let height = app.form.fields.len() as u16 + 5;
+        let area = centered_box(70, height, f.area());
         f.render_widget(Clear, area);
-        
-        if app.mode == Mode::Add {
-            draw_add_dialog(f, app, area);
-        } else if app.pending_delete {
-            draw_delete_dialog(f, app, area);
-        } else if app.show_help {
-            draw_help_dialog(f, area);
+        draw_form_dialog(f, app, area);
+        if app.picker.is_some() {
+            let area = centered_rect(50, 40, f.area());
+            f.render_widget(Clear, area);
+            draw_picker(f, app, area);
         }
+    } else if app.mode == Mode::Command {
+        let area = centered_rect(70, 20, f.area());
+        f.render_widget(Clear, area);
+        draw_command_prompt(f, app, area);
+    } else if let Some(line) = &app.dry_run {
+        let area = centered_rect(80, 30, f.area());
+        f.render_widget(Clear, area);
+        draw_dry_run_dialog(f, line, area);
+    } else if app.show_details {
+        let area = centered_rect(70, 60, f.area());
+        f.render_widget(Clear, area);
+        draw_details_dialog(f, app, area);
+    } else if app.pending_delete {
+        let area = centered_rect(80, 40, f.area());
+        f.render_widget(Clear, area);
+        draw_delete_dialog(f, app, area);
+    } else if app.show_help {
+        draw_help_dialog(f, app, f.area());
     }
 }
 
+/// Search bar and list only, for the inline `pick` mode.
+pub fn draw_pick_ui(f: &mut Frame, app: &mut AppState) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(3), Constraint::Min(1)])
+        .split(f.area());
+    draw_search_bar(f, app, chunks[0]);
+    draw_connections_list(f, app, chunks[1]);
+}
+
 fn draw_search_bar(f: &mut Frame, app: &AppState, area: Rect) {
     let search_title = match app.mode {
         Mode::Search => " 🔍 Search (ESC to cancel) ",
@@ -75,35 +117,116 @@ fn draw_search_bar(f: &mut Frame, app: &AppState, area: Rect) {
 }
 
 fn draw_connections_list(f: &mut Frame, app: &mut AppState, area: Rect) {
+    let searching = !app.search.trim().is_empty();
     let items: Vec<ListItem> = app
-        .filtered_indices
+        .rows
         .iter()
-        .enumerate()
-        .map(|(_idx, &i)| {
+        .map(|row| {
+            let (i, depth) = match row {
+                Row::Group { path, depth, count } => {
+                    let name = path.rsplit('/').next().unwrap_or(path);
+                    let arrow = if app.collapsed.contains(path) { "▸" } else { "▾" };
+                    return ListItem::new(Line::from(vec![
+                        Span::raw("  ".repeat(*depth)),
+                        Span::styled(
+                            format!("{} {}/", arrow, name),
+                            Style::default().fg(Color::Blue).add_modifier(Modifier::BOLD)
+                        ),
+                        Span::styled(
+                            format!("  ({})", count),
+                            Style::default().fg(Color::DarkGray)
+                        ),
+                    ]));
+                }
+                Row::Connection { index, depth } => (*index, *depth),
+            };
             let conn = &app.connections[i];
-            let port = conn.port.map(|p| format!(":{}", p)).unwrap_or_default();
+            let matched = app.match_indices.get(&i).map(Vec::as_slice).unwrap_or_default();
+            let hl = |part| part_indices(conn, matched, part);
+            let indent = "  ".repeat(depth);
+            // Search results are flat, so say where each match lives
+            let group_len = if searching && conn.group.is_some() {
+                conn.group_name().chars().count() + 1
+            } else {
+                0
+            };
+            let width = 20usize.saturating_sub(indent.len() + group_len);
             
-            let mut spans = vec![
-                Span::styled(
-                    format!("{:<20}", conn.name),
-                    Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)
-                ),
-                Span::raw("  "),
-                Span::styled(
-                    conn.user.clone(),
+            let mut spans = vec![Span::raw(indent)];
+            if group_len > 0 {
+                spans.extend(highlight_spans(
+                    &conn.group_name(),
+                    &hl(Part::Group),
+                    Style::default().fg(Color::Blue)
+                ));
+                spans.push(Span::styled("/", Style::default().fg(Color::Blue)));
+            }
+            spans.extend(highlight_spans(
+                &conn.name,
+                &hl(Part::Name),
+                Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)
+            ));
+            spans.push(Span::raw(format!(
+                "{:<pad$}  ",
+                "",
+                pad = width.saturating_sub(conn.name.chars().count())
+            )));
+            if !conn.user.is_empty() {
+                spans.extend(highlight_spans(
+                    &conn.user,
+                    &hl(Part::User),
                     Style::default().fg(Color::Green)
-                ),
-                Span::styled("@", Style::default().fg(Color::Gray)),
-                Span::styled(
-                    format!("{}{}", conn.host, port),
-                    Style::default().fg(Color::Yellow)
-                ),
-            ];
+                ));
+                spans.push(Span::styled("@", Style::default().fg(Color::Gray)));
+            }
+            spans.extend(highlight_spans(
+                &conn.host_port(),
+                &hl(Part::Host),
+                Style::default().fg(Color::Yellow)
+            ));
             
-            if conn.term.is_some() {
+            for (t, tag) in conn.tags.iter().enumerate() {
+                let chip = Style::default().fg(Color::Black).bg(tag_color(tag));
+                spans.push(Span::raw(" "));
+                spans.push(Span::styled(" ", chip));
+                spans.extend(highlight_spans(tag, &hl(Part::Tag(t)), chip));
+                spans.push(Span::styled(" ", chip));
+            }
+            
+            if !conn.transport.is_ssh() {
                 spans.push(Span::raw("  "));
                 spans.push(Span::styled(
-                    format!("[{}]", conn.term.as_ref().unwrap()),
+                    format!("⟿ {}", conn.transport.label()),
+                    Style::default().fg(Color::LightGreen)
+                ));
+            }
+
+            if !conn.forwards.is_empty() {
+                spans.push(Span::raw("  "));
+                spans.push(Span::styled(
+                    format!("⇄{}", conn.forwards.len()),
+                    Style::default().fg(Color::LightBlue)
+                ));
+            }
+            
+            if !conn.jump_hosts.is_empty() {
+                let chain = match jump_chain(conn, &app.connections) {
+                    Ok(hops) => hops
+                        .iter()
+                        .map(|h| h.name())
+                        .chain([conn.name.as_str()])
+                        .collect::<Vec<_>>()
+                        .join(" → "),
+                    Err(e) => format!("✗ {}", e),
+                };
+                spans.push(Span::raw("  "));
+                spans.push(Span::styled(chain, Style::default().fg(Color::Magenta)));
+            }
+            
+            if let Some(term) = conn.term() {
+                spans.push(Span::raw("  "));
+                spans.push(Span::styled(
+                    format!("[{}]", term),
                     Style::default().fg(Color::DarkGray)
                 ));
             }
@@ -114,6 +237,11 @@ fn draw_connections_list(f: &mut Frame, app: &mut AppState, area: Rect) {
 
     let list_block = Block::default()
         .title(" 🖥️  Connections ")
+        .title_top(
+            Line::from(if app.sort_by_name { " by name " } else { " by frecency " })
+                .right_aligned()
+                .style(Style::default().fg(Color::DarkGray))
+        )
         .borders(Borders::ALL)
         .border_type(if app.mode == Mode::Normal {
             BorderType::Thick
@@ -137,28 +265,139 @@ fn draw_connections_list(f: &mut Frame, app: &mut AppState, area: Rect) {
     f.render_stateful_widget(list, area, &mut tui_list_state(app.selected));
 }
 
+/// Split `text` into spans, emphasising the chars at `matched` positions.
+fn highlight_spans(text: &str, matched: &[usize], style: Style) -> Vec<Span<'static>> {
+    let hit = style.add_modifier(Modifier::UNDERLINED | Modifier::BOLD).fg(Color::LightRed);
+    let mut spans: Vec<Span> = Vec::new();
+    let mut run = String::new();
+    let mut run_hit = false;
+    for (i, ch) in text.chars().enumerate() {
+        let is_hit = matched.contains(&i);
+        if is_hit != run_hit && !run.is_empty() {
+            spans.push(Span::styled(std::mem::take(&mut run), if run_hit { hit } else { style }));
+        }
+        run_hit = is_hit;
+        run.push(ch);
+    }
+    if !run.is_empty() {
+        spans.push(Span::styled(run, if run_hit { hit } else { style }));
+    }
+    spans
+}
+
+/// Stable colour per tag name, so the same tag looks the same on every row.
+fn tag_color(tag: &str) -> Color {
+    const PALETTE: [Color; 6] = [
+        Color::LightBlue,
+        Color::LightGreen,
+        Color::LightMagenta,
+        Color::LightCyan,
+        Color::LightYellow,
+        Color::LightRed,
+    ];
+    let hash = tag
+        .to_lowercase()
+        .bytes()
+        .fold(0usize, |h, b| h.wrapping_mul(31).wrapping_add(b as usize));
+    PALETTE[hash % PALETTE.len()]
+}
+
+fn draw_tunnels_panel(f: &mut Frame, app: &AppState, area: Rect) {
+    let items: Vec<ListItem> = app
+        .tunnels
+        .iter()
+        .map(|t| {
+            let (dot, state) = match &t.status {
+                TunnelStatus::Running => (
+                    Span::styled("● ", Style::default().fg(Color::Green)),
+                    Span::styled(
+                        format!("up {}", format_uptime(t.uptime())),
+                        Style::default().fg(Color::Green)
+                    ),
+                ),
+                TunnelStatus::Stopped => (
+                    Span::styled("○ ", Style::default().fg(Color::DarkGray)),
+                    Span::styled("stopped", Style::default().fg(Color::DarkGray)),
+                ),
+                TunnelStatus::Exited(why) => (
+                    Span::styled("✗ ", Style::default().fg(Color::Red)),
+                    Span::styled(format!("exited: {}", why), Style::default().fg(Color::Red)),
+                ),
+            };
+            ListItem::new(Line::from(vec![
+                dot,
+                Span::styled(
+                    format!("{:<20}", t.name),
+                    Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)
+                ),
+                Span::raw("  "),
+                Span::styled(t.forwards.join(", "), Style::default().fg(Color::LightBlue)),
+                Span::raw("  "),
+                state,
+            ]))
+        })
+        .collect();
+
+    let focused = app.mode == Mode::Tunnels;
+    let block = Block::default()
+        .title(" ⇄ Tunnels ")
+        .title_bottom(if focused {
+            " x=stop/remove, r=restart, ESC=back "
+        } else {
+            " T=focus "
+        })
+        .borders(Borders::ALL)
+        .border_type(if focused { BorderType::Thick } else { BorderType::Plain })
+        .border_style(if focused {
+            Style::default().fg(Color::LightBlue)
+        } else {
+            Style::default().fg(Color::DarkGray)
+        });
+    let list = List::new(items).block(block);
+    if focused {
+        let list = list
+            .highlight_style(
+                Style::default()
+                    .bg(Color::Rgb(50, 50, 50))
+                    .add_modifier(Modifier::BOLD)
+            )
+            .highlight_symbol("▶ ");
+        f.render_stateful_widget(list, area, &mut tui_list_state(app.tunnel_selected));
+    } else {
+        f.render_widget(list, area);
+    }
+}
+
 fn draw_footer(f: &mut Frame, app: &AppState, area: Rect) {
-    let connection_count = if app.filtered_indices.is_empty() {
+    let connection_count = if app.rows.is_empty() {
         "0/0".to_string()
     } else {
         format!(
             "{}/{}",
             app.selected + 1,
-            app.filtered_indices.len()
+            app.rows.len()
         )
     };
     
-    let mut footer_spans = vec![
+    let footer_spans = vec![
         Span::styled("↑↓/jk", Style::default().fg(Color::Cyan)),
         Span::raw(": move  "),
         Span::styled("Enter", Style::default().fg(Color::Green)),
         Span::raw(": connect  "),
         Span::styled("a", Style::default().fg(Color::Yellow)),
         Span::raw(": add  "),
+        Span::styled("e", Style::default().fg(Color::Yellow)),
+        Span::raw(": edit  "),
         Span::styled("d", Style::default().fg(Color::Red)),
         Span::raw(": delete  "),
+        Span::styled("i", Style::default().fg(Color::Yellow)),
+        Span::raw(": import  "),
         Span::styled("/", Style::default().fg(Color::Magenta)),
         Span::raw(": search  "),
+        Span::styled("s", Style::default().fg(Color::Blue)),
+        Span::raw(": sort  "),
+        Span::styled("t", Style::default().fg(Color::LightBlue)),
+        Span::raw(": tunnel  "),
         Span::styled("h", Style::default().fg(Color::Blue)),
         Span::raw(": help  "),
         Span::styled("q", Style::default().fg(Color::Red)),
@@ -169,51 +408,264 @@ fn draw_footer(f: &mut Frame, app: &AppState, area: Rect) {
         ),
     ];
     
+    // On the border, so long messages are not cut off by the key hints
+    let mut block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::DarkGray));
     if !app.status.is_empty() {
-        footer_spans.push(Span::raw("  │  "));
-        footer_spans.push(Span::styled(
-            &app.status,
-            Style::default().fg(Color::Yellow).add_modifier(Modifier::ITALIC)
-        ));
+        block = block.title(
+            Line::from(Span::styled(
+                format!(" {} ", app.status),
+                Style::default().fg(Color::Yellow).add_modifier(Modifier::ITALIC)
+            ))
+            .right_aligned()
+        );
     }
-    
-    let footer = Paragraph::new(Line::from(footer_spans))
+
+    let footer = Paragraph::new(Line::from(footer_spans)).block(block);
+    f.render_widget(footer, area);
+}
+
+fn draw_form_dialog(f: &mut Frame, app: &AppState, area: Rect) {
+    let form = &app.form;
+    let mut lines = vec![Line::from("")];
+    for (i, field) in form.fields.iter().enumerate() {
+        let focused = i == form.focus;
+        let mut spans = vec![
+            Span::styled(
+                format!("{} {:>6}: ", if focused { "▶" } else { " " }, field.field.label()),
+                if focused {
+                    Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+                } else {
+                    Style::default().fg(Color::Gray)
+                },
+            ),
+            Span::styled(field.value.clone(), Style::default().fg(Color::White)),
+        ];
+        if focused {
+            spans.push(Span::styled("▏", Style::default().fg(Color::Yellow)));
+        }
+        if field.value.is_empty() {
+            spans.push(Span::styled(
+                field.field.placeholder(),
+                Style::default().fg(Color::DarkGray).add_modifier(Modifier::ITALIC)
+            ));
+        }
+        if let (true, Some(err)) = (field.touched, &field.error) {
+            spans.push(Span::raw("  "));
+            spans.push(Span::styled(
+                format!("✗ {}", err),
+                Style::default().fg(Color::Red).add_modifier(Modifier::ITALIC)
+            ));
+        } else if let Some(warn) = &field.warning {
+            spans.push(Span::raw("  "));
+            spans.push(Span::styled(
+                format!("⚠ {}", warn),
+                Style::default().fg(Color::Yellow).add_modifier(Modifier::ITALIC)
+            ));
+        }
+        lines.push(Line::from(spans));
+    }
+
+    let base = form
+        .editing
+        .and_then(|i| app.connections.get(i).cloned())
+        .unwrap_or_default();
+    lines.push(Line::from(""));
+    lines.push(Line::from(vec![
+        Span::styled("  $ ", Style::default().fg(Color::DarkGray)),
+        match command_preview(&form.apply(&base), &app.connections) {
+            Ok(cmd) => Span::styled(cmd, Style::default().fg(Color::Green)),
+            Err(e) => Span::styled(format!("✗ {}", e), Style::default().fg(Color::Red)),
+        },
+    ]));
+
+    // On a short terminal, scroll just far enough to keep the focused field
+    // in view; its line is `focus + 1`, after the leading blank line
+    let visible = area.height.saturating_sub(2);
+    let scroll = (form.focus as u16 + 2).saturating_sub(visible);
+
+    let title = if form.editing.is_some() {
+        " ✏️  Edit Connection "
+    } else {
+        " ➕ Add Connection "
+    };
+    let dlg = Paragraph::new(lines).scroll((scroll, 0)).block(
+        Block::default()
+            .title(title)
+            .title_bottom(" Tab/Shift-Tab=move, Enter=save, ESC=cancel ")
+            .borders(Borders::ALL)
+            .border_type(BorderType::Thick)
+            .border_style(Style::default().fg(Color::Yellow))
+            .style(Style::default().bg(Color::Rgb(20, 20, 30)))
+    );
+    f.render_widget(dlg, area);
+}
+
+fn draw_picker(f: &mut Frame, app: &AppState, area: Rect) {
+    let Some(picker) = &app.picker else {
+        return;
+    };
+    let items: Vec<ListItem> = picker
+        .items
+        .iter()
+        .map(|item| ListItem::new(item.as_str()))
+        .collect();
+    let list = List::new(items)
         .block(
             Block::default()
+                .title(picker.title.as_str())
+                .title_bottom(" Enter=choose, ESC=cancel ")
                 .borders(Borders::ALL)
-                .border_style(Style::default().fg(Color::DarkGray))
-        );
-    f.render_widget(footer, area);
+                .border_type(BorderType::Rounded)
+                .border_style(Style::default().fg(Color::Cyan))
+                .style(Style::default().bg(Color::Rgb(10, 20, 30)))
+        )
+        .highlight_style(
+            Style::default()
+                .bg(Color::Rgb(50, 50, 50))
+                .add_modifier(Modifier::BOLD)
+        )
+        .highlight_symbol("▶ ");
+    f.render_stateful_widget(list, area, &mut tui_list_state(picker.selected));
 }
 
-fn draw_add_dialog(f: &mut Frame, app: &AppState, area: Rect) {
-    let add = Paragraph::new(app.add_buffer.as_str())
-        .style(Style::default().fg(Color::White))
+fn draw_details_dialog(f: &mut Frame, app: &AppState, area: Rect) {
+    let Some(i) = app.selected_connection() else {
+        return;
+    };
+    let conn = &app.connections[i];
+    let row = |label: &str, value: String| {
+        Line::from(vec![
+            Span::styled(format!("  {:>8}: ", label), Style::default().fg(Color::Gray)),
+            Span::styled(value, Style::default().fg(Color::White)),
+        ])
+    };
+    let or_dash = |v: String| if v.is_empty() { "—".to_string() } else { v };
+
+    let mut lines = vec![
+        Line::from(""),
+        row("Name", conn.name.clone()),
+        row("Group", or_dash(conn.group_name())),
+        row("User", or_dash(conn.user.clone())),
+        row("Host", conn.host.clone()),
+        row("Port", conn.port.map(|p| p.to_string()).unwrap_or_else(|| "22".into())),
+        row("Term", conn.term().unwrap_or("xterm-256color").to_string()),
+        row("Tags", or_dash(conn.tags.join(", "))),
+        row("Key", or_dash(conn.identity_file.clone().unwrap_or_default())),
+        row(
+            "Via",
+            match jump_chain(conn, &app.connections) {
+                Ok(hops) => or_dash(hops.iter().map(|h| h.name()).collect::<Vec<_>>().join(" → ")),
+                Err(e) => format!("✗ {}", e),
+            },
+        ),
+        row("Client", conn.transport.to_string()),
+        row("Command", or_dash(conn.remote_command.clone().unwrap_or_default())),
+    ];
+    let env: Vec<_> = conn.env.iter().filter(|(k, _)| *k != "TERM").collect();
+    if env.is_empty() {
+        lines.push(row("Env", "—".into()));
+    }
+    for (n, (key, value)) in env.into_iter().enumerate() {
+        lines.push(row(if n == 0 { "Env" } else { "" }, format!("{}={}", key, value)));
+    }
+    if conn.forwards.is_empty() {
+        lines.push(row("Forwards", "—".into()));
+    }
+    for (n, fwd) in conn.forwards.iter().enumerate() {
+        lines.push(row(if n == 0 { "Forwards" } else { "" }, fwd.to_string()));
+    }
+    if conn.options.is_empty() {
+        lines.push(row("Options", "—".into()));
+    }
+    for (n, (key, value)) in conn.options.iter().enumerate() {
+        lines.push(row(if n == 0 { "Options" } else { "" }, format!("{}={}", key, value)));
+    }
+    let used = match app.usage.get(&conn.name) {
+        Some(u) => format!("{} time(s)", u.connect_count),
+        None => "never".into(),
+    };
+    lines.push(row("Used", used));
+    lines.push(Line::from(""));
+    lines.push(Line::from(vec![
+        Span::styled("  $ ", Style::default().fg(Color::DarkGray)),
+        match command_preview(conn, &app.connections) {
+            Ok(cmd) => Span::styled(cmd, Style::default().fg(Color::Green)),
+            Err(e) => Span::styled(format!("✗ {}", e), Style::default().fg(Color::Red)),
+        },
+    ]));
+
+    let dlg = Paragraph::new(lines).block(
+        Block::default()
+            .title(format!(" 🔎 {} ", conn.name))
+            .title_bottom(" any key to close ")
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .border_style(Style::default().fg(Color::Cyan))
+            .style(Style::default().bg(Color::Rgb(10, 20, 30)))
+    );
+    f.render_widget(dlg, area);
+}
+
+fn draw_dry_run_dialog(f: &mut Frame, line: &str, area: Rect) {
+    let lines = vec![
+        Line::from(""),
+        Line::from(Span::styled(line, Style::default().fg(Color::Green))),
+    ];
+    let dlg = Paragraph::new(lines)
+        .wrap(Wrap { trim: false })
         .block(
             Block::default()
-                .title(" ➕ Add Connection ")
-                .title_bottom(" Enter=save, ESC=cancel ")
+                .title(" $ Dry run ")
+                .title_bottom(" y=copy, any other key=close ")
                 .borders(Borders::ALL)
-                .border_type(BorderType::Thick)
-                .border_style(Style::default().fg(Color::Yellow))
-                .style(Style::default().bg(Color::Rgb(20, 20, 30)))
+                .border_type(BorderType::Rounded)
+                .border_style(Style::default().fg(Color::Green))
+                .style(Style::default().bg(Color::Rgb(10, 20, 10)))
         );
-    f.render_widget(add, area);
-    
-    // Show format hint below input
-    let hint_area = Rect {
-        x: area.x + 2,
-        y: area.y + 3,
-        width: area.width - 4,
-        height: 1,
-    };
-    let hint = Paragraph::new("Format: <name> <user>@<host>[:port] [term]")
-        .style(Style::default().fg(Color::DarkGray).add_modifier(Modifier::ITALIC));
-    f.render_widget(hint, hint_area);
+    f.render_widget(dlg, area);
+}
+
+fn draw_command_prompt(f: &mut Frame, app: &AppState, area: Rect) {
+    let name = app
+        .selected_connection()
+        .map(|i| app.connections[i].name.as_str())
+        .unwrap_or_default();
+    let mut input = vec![
+        Span::styled("  $ ", Style::default().fg(Color::DarkGray)),
+        Span::styled(app.command.as_str(), Style::default().fg(Color::White)),
+        Span::styled("▏", Style::default().fg(Color::Yellow)),
+    ];
+    if app.command.is_empty() {
+        input.push(Span::styled(
+            "empty for a login shell",
+            Style::default().fg(Color::DarkGray).add_modifier(Modifier::ITALIC)
+        ));
+    }
+    let lines = vec![
+        Line::from(""),
+        Line::from(vec![
+            Span::raw("  Run on "),
+            Span::styled(name, Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)),
+            Span::raw(":"),
+        ]),
+        Line::from(input),
+    ];
+    let dlg = Paragraph::new(lines).block(
+        Block::default()
+            .title(" ▶ Connect with command… ")
+            .title_bottom(" Enter=connect, Ctrl-U=clear, ESC=cancel ")
+            .borders(Borders::ALL)
+            .border_type(BorderType::Thick)
+            .border_style(Style::default().fg(Color::Green))
+            .style(Style::default().bg(Color::Rgb(20, 20, 30)))
+    );
+    f.render_widget(dlg, area);
 }
 
 fn draw_delete_dialog(f: &mut Frame, app: &AppState, area: Rect) {
-    let idx = app.filtered_indices.get(app.selected).cloned();
+    let idx = app.selected_connection();
     let msg = if let Some(i) = idx {
         vec![
             Line::from(vec![
@@ -247,7 +699,7 @@ fn draw_delete_dialog(f: &mut Frame, app: &AppState, area: Rect) {
     f.render_widget(dlg, area);
 }
 
-fn draw_help_dialog(f: &mut Frame, area: Rect) {
+fn draw_help_dialog(f: &mut Frame, app: &mut AppState, screen: Rect) {
     let help_text = vec![
         Line::from(vec![
             Span::styled("Jumpseat", Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)),
@@ -263,20 +715,70 @@ fn draw_help_dialog(f: &mut Frame, area: Rect) {
             Span::styled("  Enter       ", Style::default().fg(Color::Green)),
             Span::raw("Connect to selected host"),
         ]),
+        Line::from(vec![
+            Span::styled("  Space/Enter ", Style::default().fg(Color::Blue)),
+            Span::raw("Expand/collapse selected group"),
+        ]),
+        Line::from(vec![
+            Span::styled("  ←/→         ", Style::default().fg(Color::Blue)),
+            Span::raw("Collapse/expand group"),
+        ]),
         Line::from(""),
         Line::from(Span::styled("Actions", Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD))),
         Line::from(vec![
             Span::styled("  /           ", Style::default().fg(Color::Magenta)),
             Span::raw("Search (fuzzy matching)"),
         ]),
+        Line::from(vec![
+            Span::raw("              "),
+            Span::styled("name: user: host: port: term: tag: group:", Style::default().fg(Color::Gray)),
+            Span::raw(" scope a term"),
+        ]),
+        Line::from(vec![
+            Span::raw("              "),
+            Span::styled("-host:staging \"eu west\"", Style::default().fg(Color::Gray)),
+            Span::raw(" negate, exact phrase"),
+        ]),
         Line::from(vec![
             Span::styled("  a           ", Style::default().fg(Color::Yellow)),
             Span::raw("Add new connection"),
         ]),
+        Line::from(vec![
+            Span::styled("  e           ", Style::default().fg(Color::Yellow)),
+            Span::raw("Edit selected connection"),
+        ]),
+        Line::from(vec![
+            Span::styled("  v           ", Style::default().fg(Color::Yellow)),
+            Span::raw("Show everything about the selected connection"),
+        ]),
+        Line::from(vec![
+            Span::styled("  p           ", Style::default().fg(Color::Green)),
+            Span::raw("Show (and y: copy) the exact ssh command, without connecting"),
+        ]),
+        Line::from(vec![
+            Span::styled("  c           ", Style::default().fg(Color::Green)),
+            Span::raw("Connect with a one-off remote command"),
+        ]),
         Line::from(vec![
             Span::styled("  d           ", Style::default().fg(Color::Red)),
             Span::raw("Delete selected connection"),
         ]),
+        Line::from(vec![
+            Span::styled("  i           ", Style::default().fg(Color::Yellow)),
+            Span::raw("Import hosts from ~/.ssh/config"),
+        ]),
+        Line::from(vec![
+            Span::styled("  t           ", Style::default().fg(Color::LightBlue)),
+            Span::raw("Hold the selected host's forwards open in the background"),
+        ]),
+        Line::from(vec![
+            Span::styled("  T           ", Style::default().fg(Color::LightBlue)),
+            Span::raw("Focus the Tunnels panel (x: stop/remove, r: restart)"),
+        ]),
+        Line::from(vec![
+            Span::styled("  s           ", Style::default().fg(Color::Blue)),
+            Span::raw("Toggle sort: frecency (most used recently) / name"),
+        ]),
         Line::from(vec![
             Span::styled("  h           ", Style::default().fg(Color::Blue)),
             Span::raw("Toggle this help"),
@@ -286,15 +788,30 @@ fn draw_help_dialog(f: &mut Frame, area: Rect) {
             Span::raw("Quit application"),
         ]),
         Line::from(""),
-        Line::from(Span::styled("Add Format", Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD))),
-        Line::from(Span::styled("  <name> <user>@<host>[:port] [term]", Style::default().fg(Color::Gray))),
-        Line::from(""),
-        Line::from(Span::styled("Example", Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD))),
-        Line::from(Span::styled("  myserver alice@192.168.1.10:22 xterm-256color", Style::default().fg(Color::Gray))),
+        Line::from(Span::styled("Add / Edit Form", Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD))),
+        Line::from(Span::styled("  Tab/Shift-Tab to move between fields, Enter to save", Style::default().fg(Color::Gray))),
+        Line::from(Span::styled("  Host also accepts user@host:port, e.g. alice@192.168.1.10:22", Style::default().fg(Color::Gray))),
     ];
-    let dlg = Paragraph::new(help_text).block(
+    let width = help_text.iter().map(Line::width).max().unwrap_or(0) as u16 + 2;
+    let area = centered_box(100, help_text.len() as u16 + 2, screen);
+    let area = Rect {
+        x: area.x + area.width.saturating_sub(width) / 2,
+        width: width.min(area.width),
+        ..area
+    };
+    f.render_widget(Clear, area);
+    // Scroll only as far as the last line
+    let hidden = (help_text.len() as u16 + 2).saturating_sub(area.height);
+    app.help_scroll = app.help_scroll.min(hidden);
+    let hint = if hidden > 0 {
+        " ↑↓/PgUp/PgDn=scroll, any other key=close "
+    } else {
+        " any key=close "
+    };
+    let dlg = Paragraph::new(help_text).scroll((app.help_scroll, 0)).block(
         Block::default()
             .title(" ❓ Help ")
+            .title_bottom(hint)
             .borders(Borders::ALL)
             .border_type(BorderType::Rounded)
             .border_style(Style::default().fg(Color::Blue))
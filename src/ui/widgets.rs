@@ -34,4 +34,16 @@ pub fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
         )
         .split(popup_layout[1])[1];
     area
-}
\ No newline at end of file
+}
+
+/// Like `centered_rect`, but `height` rows tall (clamped to `r`), for
+/// dialogs sized to their content.
+pub fn centered_box(percent_x: u16, height: u16, r: Rect) -> Rect {
+    let height = height.min(r.height);
+    let area = centered_rect(percent_x, 100, r);
+    Rect {
+        y: r.y + (r.height - height) / 2,
+        height,
+        ..area
+    }
+}